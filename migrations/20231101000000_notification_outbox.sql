@@ -0,0 +1,20 @@
+-- Announcements waiting to be delivered, one row per notifier and domain. The tables
+-- of the service are kept in their own schema, apart from the registration service.
+CREATE TABLE IF NOT EXISTS ordinalswallet_sync.notification_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    notifier VARCHAR NOT NULL,
+    domain VARCHAR NOT NULL,
+    reveal_tx VARCHAR NOT NULL,
+    registered_at TIMESTAMP NOT NULL,
+    status VARCHAR NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    delivered_at TIMESTAMP,
+    UNIQUE (notifier, reveal_tx)
+);
+
+CREATE INDEX IF NOT EXISTS notification_outbox_pending_idx
+    ON ordinalswallet_sync.notification_outbox (next_attempt_at)
+    WHERE status = 'pending';
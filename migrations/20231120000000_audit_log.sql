@@ -0,0 +1,19 @@
+-- Every attempt to publish or announce domains, for support questions.
+CREATE TABLE IF NOT EXISTS ordinalswallet_sync.audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    -- 'publish' or 'announce'.
+    kind VARCHAR NOT NULL,
+    -- The URL of the API, or the key of the notifier.
+    target VARCHAR NOT NULL,
+    domains VARCHAR[] NOT NULL,
+    inscription_ids VARCHAR[] NOT NULL,
+    payload_hash VARCHAR,
+    status INTEGER,
+    response TEXT,
+    error TEXT,
+    duration_ms BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_domains_idx ON ordinalswallet_sync.audit_log USING GIN (domains);
+CREATE INDEX IF NOT EXISTS audit_log_inscription_ids_idx ON ordinalswallet_sync.audit_log USING GIN (inscription_ids);
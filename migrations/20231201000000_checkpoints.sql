@@ -0,0 +1,7 @@
+-- The progress of the sync and the digests, shared by the instances so a standby
+-- that becomes the leader continues where the previous leader stopped.
+CREATE TABLE IF NOT EXISTS ordinalswallet_sync.checkpoints (
+    name VARCHAR PRIMARY KEY,
+    at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
@@ -0,0 +1,11 @@
+-- Malformed domains that are not published, kept until an operator releases them.
+-- A released domain is read again from the registration tables on the next cycle,
+-- published once it is valid and then removed.
+CREATE TABLE IF NOT EXISTS ordinalswallet_sync.quarantine (
+    reveal_tx VARCHAR PRIMARY KEY,
+    domain VARCHAR NOT NULL,
+    registered_at TIMESTAMP NOT NULL,
+    reason TEXT NOT NULL,
+    quarantined_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    released_at TIMESTAMP
+);
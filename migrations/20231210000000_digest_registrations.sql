@@ -0,0 +1,9 @@
+-- The domains counted by each digest. The registration time of a domain is the last
+-- update of its payment, so a domain whose payment is updated would otherwise be
+-- counted again by a later digest.
+CREATE TABLE IF NOT EXISTS ordinalswallet_sync.digest_registrations (
+    digest VARCHAR NOT NULL,
+    reveal_tx VARCHAR NOT NULL,
+    counted_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    PRIMARY KEY (digest, reveal_tx)
+);
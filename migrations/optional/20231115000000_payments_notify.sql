@@ -0,0 +1,19 @@
+-- Optional, notifies the sync service when payments are inserted or updated so new
+-- registrations are published right away. Used with LISTEN_CHANNEL=payments_updated.
+-- The payments table belongs to the registration service, so this is not applied on
+-- startup. Apply it once with:
+--   psql "$DATABASE_URL" -f migrations/optional/20231115000000_payments_notify.sql
+CREATE OR REPLACE FUNCTION notify_payments_updated() RETURNS trigger AS $$
+BEGIN
+    -- Identical notifications of one transaction are delivered once.
+    PERFORM pg_notify('payments_updated', '');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS payments_updated_notify ON payments;
+
+CREATE TRIGGER payments_updated_notify
+    AFTER INSERT OR UPDATE ON payments
+    FOR EACH STATEMENT
+    EXECUTE FUNCTION notify_payments_updated();
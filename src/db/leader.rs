@@ -0,0 +1,160 @@
+use sha2::{Digest, Sha256};
+use sqlx::{Connection, PgConnection, PgPool};
+use tracing::{error, info};
+
+use crate::server::metrics::metrics;
+
+/// Elects the instance that syncs the domains of a collection.
+///
+/// The leader holds a Postgres advisory lock keyed by the slug on a dedicated
+/// connection. The lock is released when that connection closes, so a standby can
+/// take over when the leader stops or loses its database connection.
+pub struct Leader {
+    pool: PgPool,
+    key: i64,
+    instance: String,
+    connection: Option<PgConnection>,
+}
+
+impl Leader {
+    pub fn new(pool: PgPool, slug: &str) -> Self {
+        Self {
+            pool,
+            key: lock_key(slug),
+            instance: instance_name(),
+            connection: None,
+        }
+    }
+
+    /// Tries to become the leader, or checks that the leadership was not lost.
+    ///
+    /// Returns whether this instance is the leader.
+    pub async fn try_acquire(&mut self) -> bool {
+        if self.connection.is_some() {
+            return self.is_held().await;
+        }
+
+        match self.acquire().await {
+            Ok(true) => {
+                info!("[LEADER] {} became the leader", self.instance);
+                metrics().leader.set(1);
+                true
+            }
+            Ok(false) => false,
+            Err(e) => {
+                error!("[LEADER] Error acquiring the leader lock: {:?}", e);
+                false
+            }
+        }
+    }
+
+    async fn acquire(&mut self) -> sqlx::Result<bool> {
+        // The lock belongs to the session, so the connection is kept out of the pool.
+        let mut connection = self.pool.acquire().await?.detach();
+
+        // Names the session, so the leader can be looked up in `pg_stat_activity`.
+        sqlx::query("SELECT set_config('application_name', $1, false)")
+            .bind(&self.instance)
+            .execute(&mut connection)
+            .await?;
+
+        let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+            .bind(self.key)
+            .fetch_one(&mut connection)
+            .await?;
+
+        if acquired {
+            self.connection = Some(connection);
+        } else {
+            connection.close().await?;
+        }
+
+        Ok(acquired)
+    }
+
+    /// Checks that the connection holding the lock is still alive.
+    pub async fn is_held(&mut self) -> bool {
+        let Some(connection) = self.connection.as_mut() else {
+            return false;
+        };
+
+        match connection.ping().await {
+            Ok(_) => true,
+            Err(e) => {
+                error!("[LEADER] Lost the leadership: {:?}", e);
+                self.connection = None;
+                metrics().leader.set(0);
+                false
+            }
+        }
+    }
+
+    /// Releases the lock, so a standby can take over right away.
+    pub async fn release(&mut self) {
+        let Some(mut connection) = self.connection.take() else {
+            return;
+        };
+
+        metrics().leader.set(0);
+
+        let unlocked = sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(self.key)
+            .execute(&mut connection)
+            .await;
+        let closed = connection.close().await;
+
+        match unlocked.and(closed) {
+            Ok(_) => info!("[LEADER] Released the leadership"),
+            Err(e) => error!("[LEADER] Error releasing the leader lock: {:?}", e),
+        }
+    }
+}
+
+/// Returns the name of the instance holding the leader lock, if any.
+pub async fn current_leader(pool: &PgPool, slug: &str) -> sqlx::Result<Option<String>> {
+    // A bigint advisory lock is stored as its high and low 32 bits.
+    sqlx::query_scalar(
+        r#"SELECT pg_stat_activity.application_name FROM pg_locks
+           INNER JOIN pg_stat_activity ON pg_stat_activity.pid = pg_locks.pid
+           WHERE pg_locks.locktype = 'advisory' AND pg_locks.granted AND pg_locks.objsubid = 1
+             AND ((pg_locks.classid::bigint << 32) | pg_locks.objid::bigint) = $1
+           LIMIT 1;"#,
+    )
+    .bind(lock_key(slug))
+    .fetch_optional(pool)
+    .await
+}
+
+/// The name of this instance, its host name and process id.
+pub fn instance_name() -> String {
+    let hostname = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|hostname| hostname.trim().to_string())
+        .filter(|hostname| !hostname.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    format!("{}-{}", hostname, std::process::id())
+}
+
+/// The key of the advisory lock of a collection.
+fn lock_key(slug: &str) -> i64 {
+    let hash = Sha256::digest(format!("ordinalswallet-sync:{}", slug).as_bytes());
+    i64::from_be_bytes(hash[..8].try_into().expect("a SHA-256 hash has 32 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leader_lock_key_is_keyed_by_slug() {
+        assert_eq!(lock_key("xiler-dns"), lock_key("xiler-dns"));
+        assert_ne!(lock_key("xiler-dns"), lock_key("xiler-dns-signet"));
+    }
+
+    #[test]
+    fn leader_instance_name_has_process_id() {
+        assert!(instance_name().ends_with(&format!("-{}", std::process::id())));
+    }
+}
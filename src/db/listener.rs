@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use sqlx::postgres::PgListener;
+use tracing::{debug, error};
+
+use crate::utils::shutdown::Shutdown;
+
+/// Wakes the sync loop when the database notifies that payments were updated.
+///
+/// Without a listener it only sleeps, so polling stays the fallback when the
+/// notification trigger is not installed or the connection is lost.
+pub struct Wakeup {
+    listener: Option<PgListener>,
+    /// How long to wait for more notifications after the first, to batch bursts.
+    debounce: Duration,
+}
+
+enum Woken {
+    Elapsed,
+    Shutdown,
+    Notified,
+    Failed(sqlx::Error),
+}
+
+impl Wakeup {
+    pub fn new(listener: Option<PgListener>, debounce: Duration) -> Self {
+        Self { listener, debounce }
+    }
+
+    /// Waits for a notification, at most the given amount of seconds, or until the
+    /// shutdown is requested.
+    pub async fn wait(&mut self, seconds: u64, shutdown: &mut Shutdown) {
+        let Some(listener) = self.listener.as_mut() else {
+            shutdown.sleep(seconds).await;
+            return;
+        };
+
+        let timeout = tokio::time::sleep(Duration::from_secs(seconds));
+        tokio::pin!(timeout);
+
+        let woken = tokio::select! {
+            _ = &mut timeout => Woken::Elapsed,
+            _ = shutdown.requested() => Woken::Shutdown,
+            notification = listener.recv() => match notification {
+                Ok(_) => Woken::Notified,
+                Err(e) => Woken::Failed(e),
+            },
+        };
+
+        match woken {
+            Woken::Elapsed | Woken::Shutdown => {}
+            Woken::Notified => {
+                debug!("[DB] Woken up by a payment notification");
+                self.debounce(shutdown).await;
+            }
+            Woken::Failed(e) => {
+                error!("[DB] Error listening for notifications: {:?}", e);
+                // Polls for the rest of the wait, the listener reconnects on the next one.
+                tokio::select! {
+                    _ = &mut timeout => {}
+                    _ = shutdown.requested() => {}
+                }
+            }
+        }
+    }
+
+    /// Consumes the notifications of a burst during the debounce window, so they are
+    /// synced in one cycle.
+    async fn debounce(&mut self, shutdown: &mut Shutdown) {
+        let Some(listener) = self.listener.as_mut() else {
+            return;
+        };
+
+        let window = tokio::time::sleep(self.debounce);
+        tokio::pin!(window);
+
+        let mut count = 1;
+        loop {
+            tokio::select! {
+                _ = &mut window => break,
+                _ = shutdown.requested() => break,
+                notification = listener.recv() => match notification {
+                    Ok(_) => count += 1,
+                    Err(_) => break,
+                },
+            }
+        }
+
+        debug!("[DB] Batched {} payment notifications", count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn wakeup_without_listener_sleeps() {
+        let (sender, mut shutdown) = Shutdown::channel();
+        let mut wakeup = Wakeup::new(None, Duration::from_secs(1));
+
+        let started = tokio::time::Instant::now();
+        wakeup.wait(0, &mut shutdown).await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        sender.send_replace(true);
+        tokio::time::timeout(Duration::from_secs(5), wakeup.wait(60, &mut shutdown))
+            .await
+            .expect("the wait ends on shutdown");
+    }
+}
@@ -1,5 +1,10 @@
+pub mod leader;
+pub mod listener;
 pub mod repository;
 pub mod traits;
 
 pub use repository::Repository;
-pub use traits::DomainsRepository;
+pub use traits::{
+    AuditRepository, CheckpointRepository, DigestRepository, DomainsRepository, OutboxRepository,
+    QuarantineRepository,
+};
@@ -0,0 +1,300 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::{
+    db::{
+        AuditRepository, CheckpointRepository, DigestRepository, DomainsRepository,
+        OutboxRepository, QuarantineRepository,
+    },
+    models::{
+        audit_entry::AuditEntry, checkpoint::Checkpoint, outbox_message::OutboxMessage,
+        quarantined_domain::QuarantinedDomain, registered_domain::RegisteredDomain,
+    },
+};
+
+/// The id of a failed outbox message, with the time of its retry.
+type FailedMessage = (i64, Option<NaiveDateTime>);
+
+/// Keeps the registered domains, the outbox, the checkpoints, the domains counted
+/// by the digests and the audit log in memory, for tests of the sync cycle and the digests.
+///
+/// An outbox message stays pending until it is marked delivered or failed, a retry
+/// is never due. Clones share the same data, like instances sharing a database.
+#[derive(Default, Clone)]
+pub struct MemoryRepository {
+    pub domains: Arc<Mutex<Vec<RegisteredDomain>>>,
+    pub outbox: Arc<Mutex<Vec<OutboxMessage>>>,
+    /// The ids of the delivered outbox messages.
+    pub delivered: Arc<Mutex<Vec<i64>>>,
+    pub failed: Arc<Mutex<Vec<FailedMessage>>>,
+    pub checkpoints: Arc<Mutex<HashMap<String, DateTime<Utc>>>>,
+    /// The names of the digests with the reveal transactions of the counted domains.
+    pub digest_registrations: Arc<Mutex<HashSet<(String, String)>>>,
+    pub quarantine: Arc<Mutex<Vec<QuarantinedDomain>>>,
+    pub audit_log: Arc<Mutex<Vec<AuditEntry>>>,
+    /// Makes adding announcements to the outbox fail, as if the database was down.
+    pub fail_enqueue: Arc<AtomicBool>,
+}
+
+impl MemoryRepository {
+    pub fn new(domains: Vec<RegisteredDomain>) -> Self {
+        Self {
+            domains: Arc::new(Mutex::new(domains)),
+            ..Self::default()
+        }
+    }
+}
+
+impl DomainsRepository for MemoryRepository {
+    async fn get_domains_since(&self, since: NaiveDateTime) -> sqlx::Result<Vec<RegisteredDomain>> {
+        Ok(self
+            .domains
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|domain| domain.registered_at.naive_utc() > since)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_domains_between(
+        &self,
+        since: NaiveDateTime,
+        until: NaiveDateTime,
+    ) -> sqlx::Result<Vec<RegisteredDomain>> {
+        Ok(self
+            .get_domains_since(since)
+            .await?
+            .into_iter()
+            .filter(|domain| domain.registered_at.naive_utc() <= until)
+            .collect())
+    }
+
+    async fn count_domains(&self) -> sqlx::Result<i64> {
+        Ok(self.domains.lock().unwrap().len() as i64)
+    }
+}
+
+impl OutboxRepository for MemoryRepository {
+    async fn enqueue_notifications(
+        &self,
+        notifications: &[(String, RegisteredDomain)],
+        checkpoint: &Checkpoint,
+    ) -> sqlx::Result<()> {
+        if self.fail_enqueue.load(Ordering::SeqCst) {
+            return Err(sqlx::Error::PoolTimedOut);
+        }
+
+        {
+            let mut outbox = self.outbox.lock().unwrap();
+
+            for (notifier, domain) in notifications {
+                let id = outbox.len() as i64 + 1;
+                outbox.push(OutboxMessage {
+                    id,
+                    notifier: notifier.clone(),
+                    domain: domain.clone(),
+                    attempts: 0,
+                });
+            }
+        }
+
+        self.set_checkpoint(checkpoint).await
+    }
+
+    async fn get_pending_notifications(
+        &self,
+        notifiers: &[String],
+        limit: i64,
+    ) -> sqlx::Result<Vec<OutboxMessage>> {
+        let delivered = self.delivered.lock().unwrap();
+        let failed = self.failed.lock().unwrap();
+
+        Ok(self
+            .outbox
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| notifiers.contains(&m.notifier))
+            .filter(|m| !delivered.contains(&m.id))
+            .filter(|m| !failed.iter().any(|(id, _)| *id == m.id))
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn mark_delivered(&self, ids: &[i64]) -> sqlx::Result<()> {
+        self.delivered.lock().unwrap().extend_from_slice(ids);
+        Ok(())
+    }
+
+    async fn fail_unknown_notifications(
+        &self,
+        notifiers: &[String],
+        _error: &str,
+    ) -> sqlx::Result<Vec<String>> {
+        let mut failed = self.failed.lock().unwrap();
+        let unknown = self
+            .outbox
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| !notifiers.contains(&m.notifier))
+            .filter(|m| !failed.iter().any(|(id, _)| *id == m.id))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        failed.extend(unknown.iter().map(|m| (m.id, None)));
+        Ok(unknown.into_iter().map(|m| m.notifier).collect())
+    }
+
+    async fn mark_failed(
+        &self,
+        ids: &[i64],
+        _error: &str,
+        retry_at: Option<NaiveDateTime>,
+    ) -> sqlx::Result<()> {
+        self.failed
+            .lock()
+            .unwrap()
+            .extend(ids.iter().map(|id| (*id, retry_at)));
+        Ok(())
+    }
+}
+
+impl CheckpointRepository for MemoryRepository {
+    async fn get_checkpoint(&self, name: &str) -> sqlx::Result<Option<DateTime<Utc>>> {
+        Ok(self.checkpoints.lock().unwrap().get(name).copied())
+    }
+
+    async fn set_checkpoint(&self, checkpoint: &Checkpoint) -> sqlx::Result<()> {
+        let mut checkpoints = self.checkpoints.lock().unwrap();
+        let at = checkpoints.entry(checkpoint.name.clone()).or_default();
+        *at = (*at).max(checkpoint.at);
+        Ok(())
+    }
+}
+
+impl DigestRepository for MemoryRepository {
+    async fn get_counted_domains(
+        &self,
+        digest: &str,
+        reveal_txs: &[String],
+    ) -> sqlx::Result<Vec<String>> {
+        let digest_registrations = self.digest_registrations.lock().unwrap();
+
+        Ok(reveal_txs
+            .iter()
+            .filter(|reveal_tx| {
+                digest_registrations.contains(&(digest.to_string(), reveal_tx.to_string()))
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn record_digest(
+        &self,
+        reveal_txs: &[String],
+        checkpoint: &Checkpoint,
+    ) -> sqlx::Result<()> {
+        self.digest_registrations.lock().unwrap().extend(
+            reveal_txs
+                .iter()
+                .map(|reveal_tx| (checkpoint.name.clone(), reveal_tx.clone())),
+        );
+        self.set_checkpoint(checkpoint).await
+    }
+}
+
+impl QuarantineRepository for MemoryRepository {
+    async fn quarantine_domain(
+        &self,
+        domain: &RegisteredDomain,
+        reason: &str,
+    ) -> sqlx::Result<bool> {
+        let mut quarantine = self.quarantine.lock().unwrap();
+        let quarantined = QuarantinedDomain {
+            domain: domain.clone(),
+            reason: reason.to_string(),
+            quarantined_at: Utc::now(),
+            released_at: None,
+        };
+
+        match quarantine
+            .iter_mut()
+            .find(|q| q.domain.reveal_tx == domain.reveal_tx)
+        {
+            Some(existing) if existing.released_at.is_none() => Ok(false),
+            Some(existing) => {
+                *existing = quarantined;
+                Ok(true)
+            }
+            None => {
+                quarantine.push(quarantined);
+                Ok(true)
+            }
+        }
+    }
+
+    async fn get_quarantined_domains(&self) -> sqlx::Result<Vec<QuarantinedDomain>> {
+        Ok(self.quarantine.lock().unwrap().clone())
+    }
+
+    async fn release_domains(&self, queries: &[String]) -> sqlx::Result<Vec<String>> {
+        let mut released = vec![];
+
+        for quarantined in self.quarantine.lock().unwrap().iter_mut() {
+            let domain = &quarantined.domain;
+            if quarantined.released_at.is_none()
+                && (queries.contains(&domain.domain) || queries.contains(&domain.inscription_id))
+            {
+                quarantined.released_at = Some(Utc::now());
+                released.push(domain.domain.clone());
+            }
+        }
+
+        Ok(released)
+    }
+
+    async fn get_released_domains(&self) -> sqlx::Result<Vec<RegisteredDomain>> {
+        let quarantine = self.quarantine.lock().unwrap();
+
+        Ok(self
+            .domains
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|domain| {
+                quarantine
+                    .iter()
+                    .any(|q| q.released_at.is_some() && q.domain.reveal_tx == domain.reveal_tx)
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn remove_from_quarantine(&self, reveal_txs: &[String]) -> sqlx::Result<()> {
+        self.quarantine
+            .lock()
+            .unwrap()
+            .retain(|q| !reveal_txs.contains(&q.domain.reveal_tx));
+        Ok(())
+    }
+}
+
+impl AuditRepository for MemoryRepository {
+    async fn record_attempt(&self, entry: &AuditEntry) -> sqlx::Result<()> {
+        self.audit_log.record_attempt(entry).await
+    }
+
+    async fn search_audit_log(&self, query: &str, limit: i64) -> sqlx::Result<Vec<AuditEntry>> {
+        self.audit_log.search_audit_log(query, limit).await
+    }
+}
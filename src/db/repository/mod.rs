@@ -1,3 +1,5 @@
+#[cfg(test)]
+pub mod memory;
 pub mod sqlx_postgresql;
 
 pub use sqlx_postgresql::SqlxPostgresqlDomainsRepository as Repository;
@@ -1,25 +1,99 @@
-use chrono::NaiveDateTime;
-use sqlx::PgPool;
-use tracing::{debug, info};
+use std::str::FromStr;
 
-use crate::db::traits::DomainsRepository;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use color_eyre::eyre::{Result, WrapErr};
+use sqlx::{
+    postgres::{PgConnectOptions, PgListener},
+    PgExecutor, PgPool,
+};
+use tracing::{debug, field, info, instrument};
 
+use crate::{
+    config::Config,
+    db::traits::{
+        AuditRepository, CheckpointRepository, DigestRepository, DomainsRepository,
+        OutboxRepository, QuarantineRepository,
+    },
+    models::{
+        audit_entry::{AuditEntry, Receipt},
+        checkpoint::Checkpoint,
+        outbox_message::{OutboxMessage, OutboxStatus},
+        quarantined_domain::QuarantinedDomain,
+        registered_domain::RegisteredDomain,
+    },
+    utils::telemetry,
+};
+
+/// The schema of the tables of this service, including its `_sqlx_migrations`.
+///
+/// The database is shared with the registration service, whose tables and
+/// migrations stay in the public schema.
+pub static SCHEMA: &str = "ordinalswallet_sync";
+
+#[derive(Clone)]
 pub struct SqlxPostgresqlDomainsRepository {
     pool: PgPool,
 }
 
 impl SqlxPostgresqlDomainsRepository {
-    pub async fn new() -> Self {
+    /// Connects to the database and runs the migrations in `migrations/`.
+    ///
+    /// Tables are looked up in [`SCHEMA`] first and then in the public schema of
+    /// the registration service. The migrations name [`SCHEMA`] explicitly, so they
+    /// never touch the tables of the registration service.
+    pub async fn new(config: &Config) -> Result<Self> {
         debug!("[DB] Connecting to Postgresql database");
 
-        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-        let pool = PgPool::connect(&url)
+        let options = PgConnectOptions::from_str(config.database_url.expose())
+            .wrap_err("Invalid DATABASE_URL")?
+            .options([("search_path", format!("{},public", SCHEMA))]);
+        let pool = PgPool::connect_with(options)
             .await
-            .expect("Failed to connect to Postgres");
+            .wrap_err("Failed to connect to Postgres")?;
 
         info!("[DB] Successfully connected to Postgresql database");
 
-        Self { pool }
+        // Creating requires a privilege on the database, a schema that was created
+        // beforehand is enough.
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_namespace WHERE nspname = $1)")
+                .bind(SCHEMA)
+                .fetch_one(&pool)
+                .await
+                .wrap_err("Failed to look up the database schema")?;
+        if !exists {
+            sqlx::query(&format!("CREATE SCHEMA {}", SCHEMA))
+                .execute(&pool)
+                .await
+                .wrap_err_with(|| format!("Failed to create the {} schema", SCHEMA))?;
+        }
+        sqlx::migrate!()
+            .run(&pool)
+            .await
+            .wrap_err("Failed to run database migrations")?;
+
+        Ok(Self { pool })
+    }
+
+    /// Closes the connections to the database, waiting for the queries in progress.
+    pub async fn close(&self) {
+        self.pool.close().await;
+        info!("[DB] Closed the connections to the Postgresql database");
+    }
+
+    /// Listens for notifications on the given channel, with its own connection.
+    pub async fn listen(&self, channel: &str) -> sqlx::Result<PgListener> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(channel).await?;
+
+        info!("[DB] Listening for notifications on {}", channel);
+
+        Ok(listener)
+    }
+
+    /// The connection pool, e.g. for the readiness checks.
+    pub fn pool(&self) -> PgPool {
+        self.pool.clone()
     }
 }
 
@@ -32,13 +106,14 @@ impl DomainsRepository for SqlxPostgresqlDomainsRepository {
     ///
     /// # Returns
     ///
-    /// A vector of tuples containing the domain and reveal transaction.
-    /// Each tuple follows this format: (domain, reveal_tx).
-    async fn get_domains_since(&self, since: NaiveDateTime) -> sqlx::Result<Vec<(String, String)>> {
+    /// A vector of the registered domains, with the time the payment of the
+    /// registration was last updated as registration time.
+    #[instrument(skip(self), fields(otel.status_message = field::Empty))]
+    async fn get_domains_since(&self, since: NaiveDateTime) -> sqlx::Result<Vec<RegisteredDomain>> {
         debug!("[DB] Getting domains since {}", since);
 
         sqlx::query!(
-            r#"SELECT private_keys.domain, payment_inscriptions.reveal_tx FROM private_keys 
+            r#"SELECT private_keys.domain, payment_inscriptions.reveal_tx, payments.updated_at FROM private_keys 
                INNER JOIN payment_inscription_contents ON payment_inscription_contents.id = private_keys.payment_inscription_content_id 
                INNER JOIN payment_inscriptions ON payment_inscriptions.content = payment_inscription_contents.id 
                INNER JOIN payments ON payments.id = payment_inscription_contents.payment_id 
@@ -46,9 +121,393 @@ impl DomainsRepository for SqlxPostgresqlDomainsRepository {
             since
         )
         .fetch_all(&self.pool)
+        .await
+        .inspect_err(telemetry::record_error)?
+        .into_iter()
+        .map(|d| Ok(RegisteredDomain::new(d.domain, d.reveal_tx, d.updated_at.and_utc())))
+        .collect()
+    }
+
+    /// Get the domains registered within a period, see `get_domains_since`.
+    #[instrument(skip(self), fields(otel.status_message = field::Empty))]
+    async fn get_domains_between(
+        &self,
+        since: NaiveDateTime,
+        until: NaiveDateTime,
+    ) -> sqlx::Result<Vec<RegisteredDomain>> {
+        debug!("[DB] Getting domains between {} and {}", since, until);
+
+        sqlx::query!(
+            r#"SELECT private_keys.domain, payment_inscriptions.reveal_tx, payments.updated_at FROM private_keys 
+               INNER JOIN payment_inscription_contents ON payment_inscription_contents.id = private_keys.payment_inscription_content_id 
+               INNER JOIN payment_inscriptions ON payment_inscriptions.content = payment_inscription_contents.id 
+               INNER JOIN payments ON payments.id = payment_inscription_contents.payment_id 
+               WHERE payments.updated_at > $1 AND payments.updated_at <= $2;"#,
+            since,
+            until
+        )
+        .fetch_all(&self.pool)
+        .await
+        .inspect_err(telemetry::record_error)?
+        .into_iter()
+        .map(|d| Ok(RegisteredDomain::new(d.domain, d.reveal_tx, d.updated_at.and_utc())))
+        .collect()
+    }
+
+    async fn count_domains(&self) -> sqlx::Result<i64> {
+        debug!("[DB] Counting domains");
+
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM private_keys 
+               INNER JOIN payment_inscription_contents ON payment_inscription_contents.id = private_keys.payment_inscription_content_id 
+               INNER JOIN payment_inscriptions ON payment_inscriptions.content = payment_inscription_contents.id;"#
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+}
+
+impl OutboxRepository for SqlxPostgresqlDomainsRepository {
+    async fn enqueue_notifications(
+        &self,
+        notifications: &[(String, RegisteredDomain)],
+        checkpoint: &Checkpoint,
+    ) -> sqlx::Result<()> {
+        debug!("[DB] Enqueueing {} notifications", notifications.len());
+
+        let (notifiers, domains): (Vec<_>, Vec<_>) = notifications.iter().cloned().unzip();
+        let reveal_txs = domains
+            .iter()
+            .map(|d| d.reveal_tx.clone())
+            .collect::<Vec<_>>();
+        let registered_at = domains
+            .iter()
+            .map(|d| d.registered_at.naive_utc())
+            .collect::<Vec<_>>();
+        let domains = domains.into_iter().map(|d| d.domain).collect::<Vec<_>>();
+
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"INSERT INTO notification_outbox (notifier, domain, reveal_tx, registered_at)
+               SELECT * FROM UNNEST($1::varchar[], $2::varchar[], $3::varchar[], $4::timestamp[])
+               ON CONFLICT (notifier, reveal_tx) DO NOTHING;"#,
+            &notifiers,
+            &domains,
+            &reveal_txs,
+            &registered_at
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        set_checkpoint(&mut *transaction, checkpoint).await?;
+
+        transaction.commit().await
+    }
+
+    async fn get_pending_notifications(
+        &self,
+        notifiers: &[String],
+        limit: i64,
+    ) -> sqlx::Result<Vec<OutboxMessage>> {
+        Ok(sqlx::query!(
+            r#"SELECT id, notifier, domain, reveal_tx, registered_at, attempts FROM notification_outbox
+               WHERE status = $1 AND notifier = ANY($2) AND next_attempt_at <= (now() AT TIME ZONE 'utc')
+               ORDER BY id
+               LIMIT $3;"#,
+            OutboxStatus::Pending.to_string(),
+            notifiers,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|m| OutboxMessage {
+            id: m.id,
+            notifier: m.notifier,
+            domain: RegisteredDomain::new(m.domain, m.reveal_tx, m.registered_at.and_utc()),
+            attempts: m.attempts,
+        })
+        .collect())
+    }
+
+    async fn mark_delivered(&self, ids: &[i64]) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"UPDATE notification_outbox
+               SET status = $1, delivered_at = (now() AT TIME ZONE 'utc'), last_error = NULL
+               WHERE id = ANY($2);"#,
+            OutboxStatus::Delivered.to_string(),
+            ids
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn fail_unknown_notifications(
+        &self,
+        notifiers: &[String],
+        error: &str,
+    ) -> sqlx::Result<Vec<String>> {
+        sqlx::query_scalar!(
+            r#"UPDATE notification_outbox
+               SET status = $1, last_error = $2
+               WHERE status = $3 AND NOT (notifier = ANY($4))
+               RETURNING notifier;"#,
+            OutboxStatus::Failed.to_string(),
+            error,
+            OutboxStatus::Pending.to_string(),
+            notifiers
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn mark_failed(
+        &self,
+        ids: &[i64],
+        error: &str,
+        retry_at: Option<NaiveDateTime>,
+    ) -> sqlx::Result<()> {
+        let status = match retry_at {
+            Some(_) => OutboxStatus::Pending,
+            None => OutboxStatus::Failed,
+        };
+
+        sqlx::query!(
+            r#"UPDATE notification_outbox
+               SET status = $1, attempts = attempts + 1, last_error = $2,
+                   next_attempt_at = COALESCE($3, next_attempt_at)
+               WHERE id = ANY($4);"#,
+            status.to_string(),
+            error,
+            retry_at,
+            ids
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl CheckpointRepository for SqlxPostgresqlDomainsRepository {
+    async fn get_checkpoint(&self, name: &str) -> sqlx::Result<Option<DateTime<Utc>>> {
+        Ok(
+            sqlx::query_scalar!(r#"SELECT at FROM checkpoints WHERE name = $1;"#, name)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|at| at.and_utc()),
+        )
+    }
+
+    async fn set_checkpoint(&self, checkpoint: &Checkpoint) -> sqlx::Result<()> {
+        set_checkpoint(&self.pool, checkpoint).await
+    }
+}
+
+/// Sets the checkpoint, also within the transaction of the outbox.
+async fn set_checkpoint<'e>(
+    executor: impl PgExecutor<'e>,
+    checkpoint: &Checkpoint,
+) -> sqlx::Result<()> {
+    debug!(
+        "[DB] Setting checkpoint {} to {}",
+        checkpoint.name, checkpoint.at
+    );
+
+    sqlx::query!(
+        r#"INSERT INTO checkpoints (name, at) VALUES ($1, $2)
+           ON CONFLICT (name) DO UPDATE
+           SET at = GREATEST(checkpoints.at, EXCLUDED.at), updated_at = (now() AT TIME ZONE 'utc');"#,
+        checkpoint.name,
+        checkpoint.at.naive_utc()
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+impl DigestRepository for SqlxPostgresqlDomainsRepository {
+    async fn get_counted_domains(
+        &self,
+        digest: &str,
+        reveal_txs: &[String],
+    ) -> sqlx::Result<Vec<String>> {
+        sqlx::query_scalar!(
+            r#"SELECT reveal_tx FROM digest_registrations WHERE digest = $1 AND reveal_tx = ANY($2);"#,
+            digest,
+            reveal_txs
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn record_digest(
+        &self,
+        reveal_txs: &[String],
+        checkpoint: &Checkpoint,
+    ) -> sqlx::Result<()> {
+        debug!(
+            "[DB] Recording {} domains of digest {}",
+            reveal_txs.len(),
+            checkpoint.name
+        );
+
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"INSERT INTO digest_registrations (digest, reveal_tx)
+               SELECT $1, * FROM UNNEST($2::varchar[])
+               ON CONFLICT (digest, reveal_tx) DO NOTHING;"#,
+            checkpoint.name,
+            reveal_txs
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        set_checkpoint(&mut *transaction, checkpoint).await?;
+
+        transaction.commit().await
+    }
+}
+
+impl QuarantineRepository for SqlxPostgresqlDomainsRepository {
+    async fn quarantine_domain(
+        &self,
+        domain: &RegisteredDomain,
+        reason: &str,
+    ) -> sqlx::Result<bool> {
+        debug!("[DB] Quarantining domain {}", domain.domain);
+
+        // A domain that is still quarantined is left as is.
+        Ok(sqlx::query_scalar!(
+            r#"INSERT INTO quarantine (reveal_tx, domain, registered_at, reason) VALUES ($1, $2, $3, $4)
+               ON CONFLICT (reveal_tx) DO UPDATE
+               SET domain = EXCLUDED.domain, registered_at = EXCLUDED.registered_at, reason = EXCLUDED.reason,
+                   quarantined_at = EXCLUDED.quarantined_at, released_at = NULL
+               WHERE quarantine.released_at IS NOT NULL
+               RETURNING reveal_tx;"#,
+            domain.reveal_tx,
+            domain.domain,
+            domain.registered_at.naive_utc(),
+            reason
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .is_some())
+    }
+
+    async fn get_quarantined_domains(&self) -> sqlx::Result<Vec<QuarantinedDomain>> {
+        Ok(sqlx::query!(
+            r#"SELECT reveal_tx, domain, registered_at, reason, quarantined_at, released_at FROM quarantine
+               ORDER BY quarantined_at;"#
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| QuarantinedDomain {
+            domain: RegisteredDomain::new(row.domain, row.reveal_tx, row.registered_at.and_utc()),
+            reason: row.reason,
+            quarantined_at: row.quarantined_at.and_utc(),
+            released_at: row.released_at.map(|at| at.and_utc()),
+        })
+        .collect())
+    }
+
+    async fn release_domains(&self, queries: &[String]) -> sqlx::Result<Vec<String>> {
+        debug!("[DB] Releasing domains {:?}", queries);
+
+        sqlx::query_scalar!(
+            r#"UPDATE quarantine SET released_at = (now() AT TIME ZONE 'utc')
+               WHERE released_at IS NULL AND (domain = ANY($1) OR reveal_tx || 'i0' = ANY($1))
+               RETURNING domain;"#,
+            queries
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_released_domains(&self) -> sqlx::Result<Vec<RegisteredDomain>> {
+        sqlx::query!(
+            r#"SELECT private_keys.domain, payment_inscriptions.reveal_tx, payments.updated_at FROM quarantine 
+               INNER JOIN payment_inscriptions ON payment_inscriptions.reveal_tx = quarantine.reveal_tx 
+               INNER JOIN payment_inscription_contents ON payment_inscription_contents.id = payment_inscriptions.content 
+               INNER JOIN private_keys ON private_keys.payment_inscription_content_id = payment_inscription_contents.id 
+               INNER JOIN payments ON payments.id = payment_inscription_contents.payment_id 
+               WHERE quarantine.released_at IS NOT NULL;"#
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|d| Ok(RegisteredDomain::new(d.domain, d.reveal_tx, d.updated_at.and_utc())))
+        .collect()
+    }
+
+    async fn remove_from_quarantine(&self, reveal_txs: &[String]) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"DELETE FROM quarantine WHERE reveal_tx = ANY($1);"#,
+            reveal_txs
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl AuditRepository for SqlxPostgresqlDomainsRepository {
+    async fn record_attempt(&self, entry: &AuditEntry) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"INSERT INTO audit_log (kind, target, domains, inscription_ids, payload_hash, status, response, error, duration_ms, created_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10);"#,
+            entry.kind.to_string(),
+            entry.target,
+            &entry.domains,
+            &entry.inscription_ids,
+            entry.receipt.payload_hash,
+            entry.receipt.status.map(i32::from),
+            entry.receipt.response,
+            entry.error,
+            entry.duration_ms,
+            entry.created_at.naive_utc()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn search_audit_log(&self, query: &str, limit: i64) -> sqlx::Result<Vec<AuditEntry>> {
+        sqlx::query!(
+            r#"SELECT kind, target, domains, inscription_ids, payload_hash, status, response, error, duration_ms, created_at FROM audit_log
+               WHERE domains @> ARRAY[$1]::varchar[] OR inscription_ids @> ARRAY[$1]::varchar[]
+               ORDER BY created_at DESC, id DESC
+               LIMIT $2;"#,
+            query,
+            limit
+        )
+        .fetch_all(&self.pool)
         .await?
         .into_iter()
-        .map(|d| Ok((d.domain, d.reveal_tx)))
+        .map(|e| {
+            Ok(AuditEntry {
+                kind: e.kind.parse().map_err(|e: String| sqlx::Error::Decode(e.into()))?,
+                target: e.target,
+                domains: e.domains,
+                inscription_ids: e.inscription_ids,
+                receipt: Receipt {
+                    payload_hash: e.payload_hash,
+                    status: e.status.map(|status| status as u16),
+                    response: e.response,
+                },
+                error: e.error,
+                duration_ms: e.duration_ms,
+                created_at: e.created_at.and_utc(),
+            })
+        })
         .collect()
     }
 }
@@ -0,0 +1,34 @@
+use crate::models::audit_entry::AuditEntry;
+
+pub trait AuditRepository {
+    /// Records an attempt to publish or announce domains.
+    async fn record_attempt(&self, entry: &AuditEntry) -> sqlx::Result<()>;
+
+    /// Returns the latest attempts that included the domain or inscription id,
+    /// newest first.
+    async fn search_audit_log(&self, query: &str, limit: i64) -> sqlx::Result<Vec<AuditEntry>>;
+}
+
+/// Keeps the audit log in memory, for tests.
+#[cfg(test)]
+impl AuditRepository for std::sync::Mutex<Vec<AuditEntry>> {
+    async fn record_attempt(&self, entry: &AuditEntry) -> sqlx::Result<()> {
+        self.lock().unwrap().push(entry.clone());
+        Ok(())
+    }
+
+    async fn search_audit_log(&self, query: &str, limit: i64) -> sqlx::Result<Vec<AuditEntry>> {
+        Ok(self
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|entry| {
+                entry.domains.iter().any(|domain| domain == query)
+                    || entry.inscription_ids.iter().any(|id| id == query)
+            })
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+}
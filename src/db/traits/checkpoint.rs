@@ -0,0 +1,12 @@
+use chrono::{DateTime, Utc};
+
+use crate::models::checkpoint::Checkpoint;
+
+pub trait CheckpointRepository {
+    /// Returns the time of the checkpoint, `None` before it is first set.
+    async fn get_checkpoint(&self, name: &str) -> sqlx::Result<Option<DateTime<Utc>>>;
+
+    /// Sets the checkpoint, it never moves back, e.g. when a former leader finishes
+    /// its cycle after a standby took over.
+    async fn set_checkpoint(&self, checkpoint: &Checkpoint) -> sqlx::Result<()>;
+}
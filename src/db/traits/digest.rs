@@ -0,0 +1,20 @@
+use crate::models::checkpoint::Checkpoint;
+
+pub trait DigestRepository {
+    /// Returns which of the domains, by their reveal transaction, were counted by an
+    /// earlier digest of the given name.
+    async fn get_counted_domains(
+        &self,
+        digest: &str,
+        reveal_txs: &[String],
+    ) -> sqlx::Result<Vec<String>>;
+
+    /// Records the domains counted by the digest of the checkpoint and sets the
+    /// checkpoint in a single transaction, so a posted digest never counts its
+    /// domains again.
+    async fn record_digest(
+        &self,
+        reveal_txs: &[String],
+        checkpoint: &Checkpoint,
+    ) -> sqlx::Result<()>;
+}
@@ -1,5 +1,22 @@
 use chrono::NaiveDateTime;
 
+use crate::models::registered_domain::RegisteredDomain;
+
 pub trait DomainsRepository {
-    async fn get_domains_since(&self, since: NaiveDateTime) -> sqlx::Result<Vec<(String, String)>>;
+    async fn get_domains_since(&self, since: NaiveDateTime) -> sqlx::Result<Vec<RegisteredDomain>>;
+
+    /// Returns the domains registered after `since` and up to `until`, e.g. for the
+    /// period of a digest.
+    ///
+    /// The registration time is the last update of the payment, a domain whose
+    /// payment is updated later is returned again for a later period, see
+    /// [`super::DigestRepository`].
+    async fn get_domains_between(
+        &self,
+        since: NaiveDateTime,
+        until: NaiveDateTime,
+    ) -> sqlx::Result<Vec<RegisteredDomain>>;
+
+    /// Returns the amount of domains that have been registered and inscribed.
+    async fn count_domains(&self) -> sqlx::Result<i64>;
 }
@@ -1,3 +1,13 @@
+pub mod audit;
+pub mod checkpoint;
+pub mod digest;
 pub mod domains;
+pub mod outbox;
+pub mod quarantine;
 
+pub use audit::AuditRepository;
+pub use checkpoint::CheckpointRepository;
+pub use digest::DigestRepository;
 pub use domains::DomainsRepository;
+pub use outbox::OutboxRepository;
+pub use quarantine::QuarantineRepository;
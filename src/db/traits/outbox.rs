@@ -0,0 +1,42 @@
+use chrono::NaiveDateTime;
+
+use crate::models::{
+    checkpoint::Checkpoint, outbox_message::OutboxMessage, registered_domain::RegisteredDomain,
+};
+
+pub trait OutboxRepository {
+    /// Adds the announcements to the outbox and sets the checkpoint in a single
+    /// transaction, so the checkpoint never passes domains that are not announced.
+    /// Announcements that are already in the outbox are ignored.
+    async fn enqueue_notifications(
+        &self,
+        notifications: &[(String, RegisteredDomain)],
+        checkpoint: &Checkpoint,
+    ) -> sqlx::Result<()>;
+
+    /// Returns the oldest pending messages of the notifiers that are due.
+    async fn get_pending_notifications(
+        &self,
+        notifiers: &[String],
+        limit: i64,
+    ) -> sqlx::Result<Vec<OutboxMessage>>;
+
+    async fn mark_delivered(&self, ids: &[i64]) -> sqlx::Result<()>;
+
+    /// Marks the pending messages of other notifiers than the given ones as failed,
+    /// and returns the notifier of every message that was marked.
+    async fn fail_unknown_notifications(
+        &self,
+        notifiers: &[String],
+        error: &str,
+    ) -> sqlx::Result<Vec<String>>;
+
+    /// Records a failed delivery attempt, the messages are retried at `retry_at` or
+    /// marked as failed when it is `None`.
+    async fn mark_failed(
+        &self,
+        ids: &[i64],
+        error: &str,
+        retry_at: Option<NaiveDateTime>,
+    ) -> sqlx::Result<()>;
+}
@@ -0,0 +1,25 @@
+use crate::models::{quarantined_domain::QuarantinedDomain, registered_domain::RegisteredDomain};
+
+pub trait QuarantineRepository {
+    /// Quarantines a malformed domain, returns whether it was not quarantined yet or
+    /// had been released.
+    async fn quarantine_domain(
+        &self,
+        domain: &RegisteredDomain,
+        reason: &str,
+    ) -> sqlx::Result<bool>;
+
+    /// Returns the quarantined domains, the oldest first.
+    async fn get_quarantined_domains(&self) -> sqlx::Result<Vec<QuarantinedDomain>>;
+
+    /// Releases the quarantined domains with the given names or inscription ids,
+    /// returns the names of the released domains.
+    async fn release_domains(&self, queries: &[String]) -> sqlx::Result<Vec<String>>;
+
+    /// Returns the released domains as they are registered now, so corrections of
+    /// their registration are picked up.
+    async fn get_released_domains(&self) -> sqlx::Result<Vec<RegisteredDomain>>;
+
+    /// Removes published domains from the quarantine, others are ignored.
+    async fn remove_from_quarantine(&self, reveal_txs: &[String]) -> sqlx::Result<()>;
+}
@@ -1,102 +1,807 @@
-#![feature(exclusive_range_pattern)]
 #![allow(async_fn_in_trait)]
 
-use db::{DomainsRepository, Repository};
-use tracing::{debug, error, info};
-use utils::last_update::get_last_update;
+use std::sync::Arc;
+
+use db::{
+    leader::{instance_name, Leader},
+    listener::Wakeup,
+    AuditRepository, CheckpointRepository, DomainsRepository, OutboxRepository,
+    QuarantineRepository, Repository,
+};
+use tracing::{debug, error, field, info, info_span, Instrument, Span};
 
 use crate::{
+    config::Config,
     models::{
-        domain_length::DomainLength,
-        inscription::{Inscription, InscriptionMetaAttribute},
+        audit_entry::{AuditEntry, AuditKind},
+        checkpoint::Checkpoint,
+        inscription::Inscription,
+    },
+    notifiers::{
+        alerts::{AlertCondition, Alerter},
+        digest::run_digest_scheduler,
+        network_name,
+        outbox::run_outbox_worker,
+        AnyNotifier, DiscordNotifier,
+    },
+    server::{
+        health::Health,
+        metrics::{metrics, ErrorKind},
     },
     utils::{
-        discord_webhook::new_domains_registered, last_update::set_last_update_to_now,
-        request::publish_inscriptions,
+        last_update::read_last_update,
+        preview::PreviewRenderer,
+        request::{publish_inscriptions, publish_url},
+        schedule::Schedule,
+        shutdown::Shutdown,
+        telemetry,
     },
 };
 
+pub mod config;
 pub mod db;
 pub mod models;
+pub mod notifiers;
+pub mod server;
 pub mod utils;
 
-async fn sleep(n: u64) {
-    tokio::time::sleep(tokio::time::Duration::from_secs(n)).await;
+/// Renders the thumbnails of the given domains to `{domain}.png`, for the collection.
+///
+/// Usage: `ordinalswallet preview <domain>...`
+fn render_thumbnails(domains: &[String]) -> color_eyre::Result<()> {
+    if domains.is_empty() {
+        color_eyre::eyre::bail!("Usage: ordinalswallet preview <domain>...");
+    }
+
+    let fonts_dir = std::env::var_os("PREVIEW_FONTS_DIR").map(std::path::PathBuf::from);
+    let previews = PreviewRenderer::new(fonts_dir.as_deref());
+
+    for domain in domains {
+        let path = format!("{}.png", domain);
+        std::fs::write(&path, previews.thumbnail(domain)?)?;
+        info!("Rendered {}", path);
+    }
+
+    Ok(())
+}
+
+/// The maximum amount of attempts listed by the `audit` command, per query.
+const AUDIT_LIMIT: i64 = 100;
+
+/// Prints the publish and announcement attempts that included the given domains or
+/// inscription ids, newest first.
+///
+/// Usage: `ordinalswallet audit <domain or inscription id>...`
+async fn search_audit_log(config: &Config, queries: &[String]) -> color_eyre::Result<()> {
+    if queries.is_empty() {
+        color_eyre::eyre::bail!("Usage: ordinalswallet audit <domain or inscription id>...");
+    }
+
+    let db = Repository::new(config).await?;
+
+    for query in queries {
+        let entries = db.search_audit_log(query, AUDIT_LIMIT).await?;
+
+        if entries.is_empty() {
+            println!("No attempts found for {}", query);
+            continue;
+        }
+
+        println!("{} attempts for {}, newest first:", entries.len(), query);
+        for entry in entries {
+            println!("{}", entry);
+        }
+    }
+
+    db.close().await;
+
+    Ok(())
+}
+
+/// Prints the quarantined domains, the oldest first.
+///
+/// Usage: `ordinalswallet quarantine`
+async fn list_quarantine(config: &Config) -> color_eyre::Result<()> {
+    let db = Repository::new(config).await?;
+    let domains = db.get_quarantined_domains().await?;
+
+    if domains.is_empty() {
+        println!("No quarantined domains");
+    }
+    for domain in domains {
+        println!("{}", domain);
+    }
+
+    db.close().await;
+
+    Ok(())
+}
+
+/// Releases quarantined domains, the sync publishes them on its next cycle once
+/// their registration is valid.
+///
+/// Usage: `ordinalswallet release <domain or inscription id>...`
+async fn release_quarantine(config: &Config, queries: &[String]) -> color_eyre::Result<()> {
+    if queries.is_empty() {
+        color_eyre::eyre::bail!("Usage: ordinalswallet release <domain or inscription id>...");
+    }
+
+    let db = Repository::new(config).await?;
+    let released = db.release_domains(queries).await?;
+
+    if released.is_empty() {
+        println!("No quarantined domains found for {}", queries.join(", "));
+    }
+    for domain in released {
+        println!("Released {}", domain);
+    }
+
+    db.close().await;
+
+    Ok(())
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> color_eyre::Result<()> {
     color_eyre::install().ok();
     dotenv::dotenv().ok();
-    tracing_subscriber::fmt::init();
+    utils::logging::init()?;
 
-    let db = Repository::new().await;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().is_some_and(|command| command == "preview") {
+        return render_thumbnails(&args[1..]);
+    }
 
-    loop {
-        let last_update = get_last_update();
-        let domains = db.get_domains_since(last_update.naive_utc()).await;
+    debug!("Loading configuration...");
+    let config = Config::load()?;
 
-        if let Err(e) = domains {
-            error!("Error getting domains: {:?}", e);
-            debug!("Retrying in 5 seconds...");
-            sleep(5).await;
+    if args.first().is_some_and(|command| command == "audit") {
+        return search_audit_log(&config, &args[1..]).await;
+    }
+    if args.first().is_some_and(|command| command == "quarantine") {
+        return list_quarantine(&config).await;
+    }
+    if args.first().is_some_and(|command| command == "release") {
+        return release_quarantine(&config, &args[1..]).await;
+    }
+
+    let mut shutdown = Shutdown::listen();
+    let shutdown_timeout = config.shutdown_timeout;
+
+    // Tag every log line with the network and instance, as several can log to one place.
+    let span = info_span!(
+        "sync",
+        network = %network_name(config.network),
+        instance = %instance_name()
+    );
+    let run = run(config, shutdown.clone()).instrument(span);
+
+    let result = tokio::select! {
+        result = run => result,
+        _ = async {
+            shutdown.requested().await;
+            tokio::time::sleep(tokio::time::Duration::from_secs(shutdown_timeout)).await;
+        } => Err(color_eyre::eyre::eyre!(
+            "The current cycle did not finish within {} seconds of the shutdown signal",
+            shutdown_timeout
+        )),
+    };
+
+    // Exports the spans of the last cycle, the exporter blocks on the runtime.
+    tokio::task::spawn_blocking(telemetry::shutdown).await?;
+
+    result
+}
+
+/// Runs the sync loop while this instance is the leader, until the shutdown is
+/// requested.
+///
+/// The cycle in progress is finished first, so published domains are always added
+/// to the outbox and the checkpoint is written. The workers then deliver the
+/// announcements that are due and the database connections are closed.
+async fn run(config: Config, mut shutdown: Shutdown) -> color_eyre::Result<()> {
+    let db = Repository::new(&config).await?;
+
+    let health = Arc::new(Health::new(db.pool(), &config));
+    if let Some(address) = config.http_address {
+        server::spawn(address, health.clone())?;
+    }
+
+    let mut alerts = Alerter::new(
+        config
+            .ops_webhook_url
+            .clone()
+            .map(|url| DiscordNotifier::new(url).with_network(config.network)),
+        config
+            .routes
+            .notifiers()
+            .filter_map(|notifier| match notifier {
+                AnyNotifier::Email(email) => Some(email.as_ref().clone()),
+                _ => None,
+            })
+            .collect(),
+        chrono::Duration::minutes(config.ops_alert_after as i64),
+    );
+
+    let mut leader = Leader::new(db.pool(), &config.slug);
+    let mut standby = false;
+
+    while !shutdown.is_requested() {
+        if !leader.try_acquire().await {
+            if !standby {
+                info!("[LEADER] Another instance is the leader, standing by");
+                standby = true;
+            }
+            shutdown.sleep(config.retry_interval).await;
             continue;
         }
 
-        let domains = domains.unwrap();
+        standby = false;
+        lead(
+            &config,
+            &db,
+            &health,
+            &mut alerts,
+            &mut leader,
+            &mut shutdown,
+        )
+        .await;
+    }
 
-        if domains.is_empty() {
-            debug!("No new domains to publish");
-            debug!("Retrying in 60 seconds...");
-            sleep(60).await;
-            continue;
+    leader.release().await;
+    db.close().await;
+    info!("[SHUTDOWN] Shut down cleanly");
+
+    Ok(())
+}
+
+/// Runs the sync loop and the workers until the leadership is lost or the shutdown
+/// is requested.
+async fn lead(
+    config: &Config,
+    db: &Repository,
+    health: &Health,
+    alerts: &mut Alerter,
+    leader: &mut Leader,
+    shutdown: &mut Shutdown,
+) {
+    // The checkpoints are read after the lock is acquired, a previous leader may
+    // have advanced them.
+    if let Err(e) = import_checkpoint(config, db).await {
+        error!("Error importing the checkpoint from its file: {:?}", e);
+    }
+
+    // Payment notifications wake the loop early, polling stays the fallback.
+    let listener = match &config.listen_channel {
+        Some(channel) => match db.listen(channel).await {
+            Ok(listener) => Some(listener),
+            Err(e) => {
+                error!(
+                    "[DB] Error listening on {}, polling instead: {:?}",
+                    channel, e
+                );
+                None
+            }
+        },
+        None => None,
+    };
+    let mut wakeup = Wakeup::new(
+        listener,
+        std::time::Duration::from_millis(config.listen_debounce),
+    );
+
+    let mut schedule = Schedule::new(config);
+
+    // The workers stop after the sync loop, to deliver the announcements of its last cycle.
+    let (stop_workers, workers) = Shutdown::channel();
+    let outbox = tokio::spawn(
+        run_outbox_worker(
+            db.clone(),
+            config.routes.clone(),
+            config.retry_interval,
+            config.notification_max_attempts,
+            workers.clone(),
+        )
+        .in_current_span(),
+    );
+    let digest = tokio::spawn(
+        run_digest_scheduler(
+            db.clone(),
+            config.digests.clone(),
+            config.network,
+            config.slug.clone(),
+            workers,
+        )
+        .in_current_span(),
+    );
+
+    let mut cycle = 0u64;
+    while !shutdown.is_requested() && leader.is_held().await {
+        cycle += 1;
+        let span = info_span!("cycle", id = cycle, inscription_ids = field::Empty);
+
+        match sync_cycle(config, db, health, alerts, &mut schedule)
+            .instrument(span)
+            .await
+        {
+            Next::Retry(retry_interval) => {
+                debug!("Retrying in {} seconds...", retry_interval);
+                shutdown.sleep(retry_interval).await;
+            }
+            Next::Poll(poll_interval) => {
+                debug!("Sleeping for {} seconds...", poll_interval);
+                wakeup.wait(poll_interval, shutdown).await;
+            }
         }
+    }
+
+    info!("Stopped the sync loop");
+    stop_workers.send_replace(true);
+    for worker in [outbox, digest] {
+        if let Err(e) = worker.await {
+            error!("Worker failed: {:?}", e);
+        }
+    }
+}
 
+/// Imports the checkpoint that was kept in `last_update_file` before it moved to
+/// the database, so an upgraded instance does not publish and announce everything
+/// again. The file is only read when the database has no checkpoint yet.
+async fn import_checkpoint<R: CheckpointRepository>(
+    config: &Config,
+    db: &R,
+) -> color_eyre::Result<()> {
+    let name = Checkpoint::sync_name(&config.slug);
+    if db.get_checkpoint(&name).await?.is_some() {
+        return Ok(());
+    }
+
+    if let Some(last_update) = read_last_update(&config.last_update_file)? {
+        info!(
+            "Importing the checkpoint {} from {}",
+            last_update,
+            config.last_update_file.display()
+        );
+        db.set_checkpoint(&Checkpoint::new(name, last_update))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// What the sync loop does after a cycle, with the amount of seconds to wait.
+enum Next {
+    /// A step failed, the cycle is retried.
+    Retry(u64),
+    /// The cycle finished, the next one polls for new domains.
+    Poll(u64),
+}
+
+/// Publishes the domains registered since the checkpoint and the released domains
+/// of the quarantine, and adds their announcements to the outbox.
+///
+/// Malformed domains are kept in the quarantine instead, until an operator
+/// releases them with the `release` command.
+///
+/// The checkpoint is kept in the database, so a new leader continues where the
+/// previous one stopped. It only advances once the announcements are in the
+/// outbox, in the same transaction, a cycle that fails before publishes the same
+/// domains again. It advances to the start of the cycle, so domains registered
+/// while the cycle runs are picked up by the next one.
+async fn sync_cycle<
+    R: DomainsRepository
+        + OutboxRepository
+        + CheckpointRepository
+        + QuarantineRepository
+        + AuditRepository,
+>(
+    config: &Config,
+    db: &R,
+    health: &Health,
+    alerts: &mut Alerter,
+    schedule: &mut Schedule,
+) -> Next {
+    let checkpoint = Checkpoint::sync_name(&config.slug);
+    let cycle_started_at = chrono::Utc::now();
+
+    let domains = async {
+        let last_update = db.get_checkpoint(&checkpoint).await?.unwrap_or_default();
+        metrics().set_checkpoint(last_update);
+        let mut domains = db.get_domains_since(last_update.naive_utc()).await?;
+        for released in db.get_released_domains().await? {
+            if !domains.iter().any(|d| d.reveal_tx == released.reveal_tx) {
+                domains.push(released);
+            }
+        }
+        Ok::<_, sqlx::Error>((last_update, domains))
+    };
+
+    let (last_update, domains) = match domains.await {
+        Ok((last_update, domains)) => {
+            alerts.recovered(AlertCondition::DatabaseUnreachable).await;
+            metrics().domains_fetched.inc_by(domains.len() as u64);
+            metrics().pending_domains.set(domains.len() as i64);
+            if !domains.is_empty() {
+                let inscription_ids = domains
+                    .iter()
+                    .map(|domain| domain.inscription_id.as_str())
+                    .collect::<Vec<_>>();
+                Span::current().record("inscription_ids", inscription_ids.join(","));
+            }
+            (last_update, domains)
+        }
+        Err(e) => {
+            error!("Error getting domains: {:?}", e);
+            metrics().error(ErrorKind::Database);
+            alerts
+                .failing(AlertCondition::DatabaseUnreachable, &e.to_string())
+                .await;
+            return Next::Retry(schedule.failed());
+        }
+    };
+
+    if domains.is_empty() {
+        alerts.recovered(AlertCondition::CheckpointStalled).await;
+        debug!("No new domains to publish");
+        schedule.succeeded();
+        return Next::Poll(schedule.next_poll());
+    }
+
+    let stalled = format!(
+        "{} domains are pending since the last update at {}",
+        domains.len(),
+        last_update
+    );
+
+    let (domains, quarantined): (Vec<_>, Vec<_>) = domains
+        .into_iter()
+        .partition(|domain| domain.validate().is_ok());
+
+    for domain in &quarantined {
+        let reason = domain.validate().unwrap_err();
+        let span = info_span!("domain", inscription_id = %domain.inscription_id);
+        let result = async {
+            // Kept before the checkpoint moves past the domain.
+            if !db.quarantine_domain(domain, &reason).await? {
+                return Ok(());
+            }
+
+            error!(
+                "Quarantining malformed domain {:?}: {}",
+                domain.domain, reason
+            );
+            metrics().error(ErrorKind::Quarantine);
+            alerts.quarantined(domain, &reason).await;
+            Ok::<_, sqlx::Error>(())
+        }
+        .instrument(span)
+        .await;
+
+        if let Err(e) = result {
+            error!("Error quarantining {:?}: {:?}", domain.domain, e);
+            metrics().error(ErrorKind::Database);
+            alerts
+                .failing(AlertCondition::CheckpointStalled, &stalled)
+                .await;
+            return Next::Retry(schedule.failed());
+        }
+    }
+
+    let checkpoint = Checkpoint::new(checkpoint, cycle_started_at);
+
+    if domains.is_empty() {
+        // Only quarantined domains, there is nothing to announce.
+        if let Err(e) = db.set_checkpoint(&checkpoint).await {
+            error!("Error setting the checkpoint: {:?}", e);
+            metrics().error(ErrorKind::Database);
+            return Next::Retry(schedule.failed());
+        }
+    } else {
         info!("Publishing {} new domains", domains.len());
         debug!("Converting domains to Inscriptions objects...");
         let inscriptions: Vec<Inscription> = domains
-            .clone()
-            .into_iter()
-            .map(|(domain, reveal_tx)| {
-                let id = format!("{}i0", reveal_tx);
-
-                let attributes = vec![InscriptionMetaAttribute::new(
-                    "length".to_string(),
-                    DomainLength::from(domain.as_str()).to_string(),
-                )];
-
-                Inscription::new(id, domain, attributes)
+            .iter()
+            .map(|domain| {
+                Inscription::new(
+                    domain.inscription_id.clone(),
+                    domain.domain.clone(),
+                    domain.attributes(),
+                )
             })
             .collect();
 
         debug!("Publishing inscriptions...");
-        match publish_inscriptions(inscriptions).await {
-            Ok(Ok(_)) => {
-                set_last_update_to_now();
-                info!("Successfully published inscriptions");
-            }
-            Ok(Err(e)) => {
-                error!("Error publishing inscriptions: {:?}", e);
-                debug!("Retrying in 5 seconds...");
-                sleep(5).await;
-                continue;
-            }
-            Err(e) => {
-                error!("HTTP Error publishing inscriptions: {:?}", e);
-                debug!("Retrying in 5 seconds...");
-                sleep(5).await;
-                continue;
-            }
+        let started_at = chrono::Utc::now();
+        let result = publish_inscriptions(config, inscriptions).await;
+
+        let entry = AuditEntry::new(
+            AuditKind::Publish,
+            publish_url(config),
+            &domains,
+            result.as_ref(),
+            started_at,
+        );
+        if let Err(e) = db.record_attempt(&entry).await {
+            error!("Error recording the publish in the audit log: {:?}", e);
+        }
+
+        let error = match result {
+            Ok(receipt) if receipt.is_success() => None,
+            Ok(receipt) => Some(format!(
+                "Error publishing inscriptions: {}: {}",
+                receipt.status.unwrap_or_default(),
+                receipt.response.unwrap_or_default()
+            )),
+            Err(e) => Some(format!("HTTP Error publishing inscriptions: {:?}", e)),
+        };
+
+        if let Some(e) = error {
+            error!("{}", e);
+            metrics().error(ErrorKind::Publish);
+            alerts.failing(AlertCondition::PublishingFailing, &e).await;
+            alerts
+                .failing(AlertCondition::CheckpointStalled, &stalled)
+                .await;
+            return Next::Retry(schedule.failed());
+        }
+
+        info!("Successfully published inscriptions");
+        metrics().domains_published.inc_by(domains.len() as u64);
+        health.published();
+        schedule.published(chrono::Utc::now());
+        alerts.recovered(AlertCondition::PublishingFailing).await;
+
+        debug!("Adding announcements to the outbox...");
+        if let Err(e) = db
+            .enqueue_notifications(&config.routes.assign(&domains), &checkpoint)
+            .await
+        {
+            error!("Error adding announcements to the outbox: {:?}", e);
+            metrics().error(ErrorKind::Outbox);
+            alerts
+                .failing(AlertCondition::CheckpointStalled, &stalled)
+                .await;
+            return Next::Retry(schedule.failed());
         }
 
-        info!("Sending Discord webhook...");
-        let domains = domains
-            .into_iter()
-            .map(|(domain, reveal_tx)| (domain, format!("{}i0", reveal_tx)))
+        let reveal_txs = domains
+            .iter()
+            .map(|domain| domain.reveal_tx.clone())
             .collect::<Vec<_>>();
+        if let Err(e) = db.remove_from_quarantine(&reveal_txs).await {
+            // They are published again by the next cycle, but not announced twice.
+            error!(
+                "Error removing published domains from the quarantine: {:?}",
+                e
+            );
+            metrics().error(ErrorKind::Database);
+        }
+    }
+
+    metrics().set_checkpoint(checkpoint.at);
+    metrics().pending_domains.set(0);
+    alerts.recovered(AlertCondition::CheckpointStalled).await;
+    schedule.succeeded();
+    Next::Poll(schedule.next_poll())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::{
+        config::test_config, db::repository::memory::MemoryRepository,
+        models::registered_domain::RegisteredDomain, notifiers::test_domains,
+        server::health::test_health,
+    };
+
+    fn test_alerts() -> Alerter {
+        Alerter::new(None, vec![], chrono::Duration::minutes(5))
+    }
+
+    #[tokio::test]
+    async fn sync_cycle_keeps_checkpoint_when_enqueue_fails() {
+        let mut api = mockito::Server::new_async().await;
+        let mock = api
+            .mock("POST", "/collection/update")
+            .with_status(200)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let config = Config {
+            api_base_url: api.url(),
+            ..test_config()
+        };
+        let checkpoint = Checkpoint::sync_name(&config.slug);
+
+        let db = MemoryRepository::new(test_domains(2));
+        let health = test_health();
+        let mut alerts = test_alerts();
+        let mut schedule = Schedule::new(&config);
+
+        db.fail_enqueue.store(true, Ordering::SeqCst);
+        let next = sync_cycle(&config, &db, &health, &mut alerts, &mut schedule).await;
+
+        assert!(matches!(next, Next::Retry(_)));
+        assert_eq!(db.get_checkpoint(&checkpoint).await.unwrap(), None);
+        assert!(db.outbox.lock().unwrap().is_empty());
+
+        // The next cycle publishes the same domains again and enqueues them.
+        db.fail_enqueue.store(false, Ordering::SeqCst);
+        let started_at = chrono::Utc::now();
+        let next = sync_cycle(&config, &db, &health, &mut alerts, &mut schedule).await;
+
+        assert!(matches!(next, Next::Poll(_)));
+        assert!(db
+            .get_checkpoint(&checkpoint)
+            .await
+            .unwrap()
+            .is_some_and(|at| at >= started_at));
+        assert_eq!(db.outbox.lock().unwrap().len(), 2);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn sync_cycle_keeps_quarantined_domains_until_released() {
+        let mut api = mockito::Server::new_async().await;
+        let mock = api
+            .mock("POST", "/collection/update")
+            .with_status(200)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let mut ops = mockito::Server::new_async().await;
+        let alerts_mock = ops
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "embeds": [{ "title": "Domain quarantined" }]
+            })))
+            .with_status(204)
+            .expect(2)
+            .create_async()
+            .await;
+        let alerter = || {
+            Alerter::new(
+                Some(DiscordNotifier::new(ops.url().into())),
+                vec![],
+                chrono::Duration::minutes(5),
+            )
+        };
+
+        let config = Config {
+            api_base_url: api.url(),
+            ..test_config()
+        };
+        let malformed = RegisteredDomain::new(
+            "malformed".to_string(),
+            "ab".repeat(32),
+            test_domains(1)[0].registered_at,
+        );
+        let mut domains = test_domains(1);
+        domains.push(malformed.clone());
+
+        let db = MemoryRepository::new(domains);
+        let health = test_health();
+        let mut schedule = Schedule::new(&config);
+
+        sync_cycle(&config, &db, &health, &mut alerter(), &mut schedule).await;
+        let quarantine = db.get_quarantined_domains().await.unwrap();
+        assert_eq!(quarantine.len(), 1);
+        assert_eq!(quarantine[0].domain, malformed);
+        assert_eq!(db.outbox.lock().unwrap().len(), 1);
+
+        // After a restart, a release of a domain that is still malformed quarantines it again.
+        assert_eq!(
+            db.release_domains(&["malformed".to_string()])
+                .await
+                .unwrap(),
+            vec!["malformed"]
+        );
+        sync_cycle(&config, &db, &health, &mut alerter(), &mut schedule).await;
+        assert!(db.get_quarantined_domains().await.unwrap()[0]
+            .released_at
+            .is_none());
+
+        // Once corrected and released, it is published and leaves the quarantine.
+        db.domains.lock().unwrap()[1].domain = "corrected.o".to_string();
+        db.release_domains(std::slice::from_ref(&malformed.inscription_id))
+            .await
+            .unwrap();
+        sync_cycle(&config, &db, &health, &mut alerter(), &mut schedule).await;
+
+        assert!(db.get_quarantined_domains().await.unwrap().is_empty());
+        assert_eq!(db.outbox.lock().unwrap()[1].domain.domain, "corrected.o");
+        mock.assert_async().await;
+        alerts_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn failover_continues_from_the_checkpoint_of_the_previous_leader() {
+        let mut api = mockito::Server::new_async().await;
+        let mock = api
+            .mock("POST", "/collection/update")
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut digest_webhook = mockito::Server::new_async().await;
+        let digest_mock = digest_webhook
+            .mock("POST", "/")
+            .with_status(204)
+            .expect(1)
+            .create_async()
+            .await;
+
+        // Both instances share the database, but not their files.
+        let config = |instance: &str| Config {
+            api_base_url: api.url(),
+            last_update_file: std::env::temp_dir().join(format!("last_update.{}.test", instance)),
+            digests: vec![toml::from_str(&format!(
+                r#"
+                    period = "daily"
+                    at = "00:00"
+                    webhook_url = "{}"
+                "#,
+                digest_webhook.url(),
+            ))
+            .unwrap()],
+            ..test_config()
+        };
+        let (first, second) = (config("first"), config("second"));
+        for config in [&first, &second] {
+            let _ = std::fs::remove_file(&config.last_update_file);
+        }
+
+        let db = MemoryRepository::new(test_domains(2));
+        let health = test_health();
+
+        // The last digest was posted two days ago, so one is due.
+        db.set_checkpoint(&Checkpoint::new(
+            Checkpoint::digest_name(&first.slug, "daily"),
+            chrono::Utc::now() - chrono::Duration::days(2),
+        ))
+        .await
+        .unwrap();
+
+        // The first leader publishes the domains and posts the digest.
+        import_checkpoint(&first, &db).await.unwrap();
+        let mut schedule = Schedule::new(&first);
+        let next = sync_cycle(&first, &db, &health, &mut test_alerts(), &mut schedule).await;
+        assert!(matches!(next, Next::Poll(_)));
+        let (stop, workers) = Shutdown::channel();
+        stop.send_replace(true);
+        run_digest_scheduler(
+            db.clone(),
+            first.digests.clone(),
+            first.network,
+            first.slug.clone(),
+            workers.clone(),
+        )
+        .await;
+
+        // The second takes over with a stale file of its own, which is not imported.
+        std::fs::write(&second.last_update_file, "1").unwrap();
+        import_checkpoint(&second, &db).await.unwrap();
+        let mut schedule = Schedule::new(&second);
+        let next = sync_cycle(&second, &db, &health, &mut test_alerts(), &mut schedule).await;
+        assert!(matches!(next, Next::Poll(_)));
+        run_digest_scheduler(
+            db.clone(),
+            second.digests.clone(),
+            second.network,
+            second.slug.clone(),
+            workers,
+        )
+        .await;
+
+        assert_eq!(db.outbox.lock().unwrap().len(), 2);
+        mock.assert_async().await;
+        digest_mock.assert_async().await;
 
-        let _ = new_domains_registered(domains).await;
-        debug!("Sleeping for 60 seconds...");
-        sleep(60).await;
+        std::fs::remove_file(&second.last_update_file).unwrap();
     }
 }
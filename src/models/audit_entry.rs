@@ -0,0 +1,213 @@
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::models::registered_domain::RegisteredDomain;
+
+/// The amount of characters of a response body kept in the audit log.
+const RESPONSE_EXCERPT_LENGTH: usize = 500;
+
+/// What was attempted, see [`AuditEntry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditKind {
+    /// Publishing inscriptions to the OrdinalsWallet API.
+    Publish,
+    /// Announcing domains through a notifier.
+    Announce,
+}
+
+impl std::fmt::Display for AuditKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Publish => write!(f, "publish"),
+            Self::Announce => write!(f, "announce"),
+        }
+    }
+}
+
+impl std::str::FromStr for AuditKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "publish" => Ok(Self::Publish),
+            "announce" => Ok(Self::Announce),
+            _ => Err(format!("Unknown audit kind {:?}", value)),
+        }
+    }
+}
+
+/// What was sent in a request and the answer to it, as recorded in the audit log.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Receipt {
+    /// The hex encoded SHA-256 of the request body, `None` for streamed bodies like
+    /// multipart uploads.
+    pub payload_hash: Option<String>,
+    /// The HTTP status, `None` without a response or for emails.
+    pub status: Option<u16>,
+    /// The start of the response body.
+    pub response: Option<String>,
+}
+
+impl Receipt {
+    pub fn new(payload: Option<&[u8]>, status: u16, body: &str) -> Self {
+        Self {
+            payload_hash: payload.map(|payload| hex::encode(Sha256::digest(payload))),
+            status: Some(status),
+            response: (!body.is_empty()).then(|| excerpt(body)),
+        }
+    }
+
+    /// Whether the status is a success, receipts without status are successes.
+    pub fn is_success(&self) -> bool {
+        self.status
+            .is_none_or(|status| (200..300).contains(&status))
+    }
+}
+
+/// A single attempt to publish or announce domains, kept so support questions like
+/// "when was this domain sent?" can be answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub kind: AuditKind,
+    /// The URL of the API for publishes, the key of the notifier for announcements.
+    pub target: String,
+    pub domains: Vec<String>,
+    pub inscription_ids: Vec<String>,
+    pub receipt: Receipt,
+    /// The error when no response was received, e.g. a connection error.
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    /// Creates the entry of an attempt that started at `started_at`, with either the
+    /// receipt of the response or the error.
+    pub fn new<E: std::fmt::Display>(
+        kind: AuditKind,
+        target: String,
+        domains: &[RegisteredDomain],
+        result: Result<&Receipt, E>,
+        started_at: DateTime<Utc>,
+    ) -> Self {
+        let (receipt, error) = match result {
+            Ok(receipt) => (receipt.clone(), None),
+            Err(e) => (Receipt::default(), Some(excerpt(&e.to_string()))),
+        };
+
+        Self {
+            kind,
+            target,
+            domains: domains.iter().map(|d| d.domain.clone()).collect(),
+            inscription_ids: domains.iter().map(|d| d.inscription_id.clone()).collect(),
+            receipt,
+            error,
+            duration_ms: (Utc::now() - started_at).num_milliseconds(),
+            created_at: started_at,
+        }
+    }
+}
+
+impl std::fmt::Display for AuditEntry {
+    /// Formats the entry as a single line for the `audit` command.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = self
+            .receipt
+            .status
+            .map_or_else(|| "-".to_string(), |status| status.to_string());
+
+        write!(
+            f,
+            "{}  {:<8}  {}  status {}  {}ms  payload {}  {}",
+            self.created_at
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            self.kind,
+            self.target,
+            status,
+            self.duration_ms,
+            self.receipt.payload_hash.as_deref().unwrap_or("-"),
+            self.domains.join(","),
+        )?;
+
+        // Quoted, as response bodies often span several lines.
+        if let Some(error) = &self.error {
+            write!(f, "\n    error: {:?}", error)?;
+        }
+        if let Some(response) = &self.receipt.response {
+            write!(f, "\n    response: {:?}", response)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn excerpt(text: &str) -> String {
+    match text.char_indices().nth(RESPONSE_EXCERPT_LENGTH) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifiers::test_domains;
+
+    #[test]
+    fn audit_kind() {
+        for kind in [AuditKind::Publish, AuditKind::Announce] {
+            assert_eq!(kind.to_string().parse::<AuditKind>(), Ok(kind));
+        }
+        assert!("deliver".parse::<AuditKind>().is_err());
+    }
+
+    #[test]
+    fn audit_receipt() {
+        let receipt = Receipt::new(Some(b"{}"), 200, "");
+        assert_eq!(
+            receipt.payload_hash.as_deref(),
+            Some("44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a")
+        );
+        assert_eq!(receipt.response, None);
+        assert!(receipt.is_success());
+
+        let receipt = Receipt::new(None, 500, &"x".repeat(1000));
+        assert!(!receipt.is_success());
+        assert_eq!(receipt.payload_hash, None);
+        assert_eq!(
+            receipt.response.unwrap().chars().count(),
+            RESPONSE_EXCERPT_LENGTH + 1
+        );
+
+        assert!(Receipt::default().is_success());
+    }
+
+    #[test]
+    fn audit_entry() {
+        let domains = test_domains(2);
+        let receipt = Receipt::new(Some(b"{}"), 204, "");
+
+        let entry = AuditEntry::new::<String>(
+            AuditKind::Announce,
+            "discord".to_string(),
+            &domains,
+            Ok(&receipt),
+            Utc::now(),
+        );
+        assert_eq!(entry.domains, vec!["domain0.o", "domain1.o"]);
+        assert_eq!(entry.inscription_ids[0], domains[0].inscription_id);
+        assert_eq!(entry.receipt, receipt);
+        assert_eq!(entry.error, None);
+
+        let entry = AuditEntry::new(
+            AuditKind::Publish,
+            "https://api.example.com/collection/update".to_string(),
+            &domains,
+            Err("connection refused"),
+            Utc::now(),
+        );
+        assert_eq!(entry.receipt, Receipt::default());
+        assert_eq!(entry.error.as_deref(), Some("connection refused"));
+        assert!(entry.to_string().contains("status -"));
+    }
+}
@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+
+/// The progress of a task, shared by the instances so a standby that becomes the
+/// leader continues where the previous leader stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub name: String,
+    pub at: DateTime<Utc>,
+}
+
+impl Checkpoint {
+    pub fn new(name: String, at: DateTime<Utc>) -> Self {
+        Self { name, at }
+    }
+
+    /// The name of the checkpoint up to which the domains of the collection are
+    /// published and announced.
+    pub fn sync_name(slug: &str) -> String {
+        format!("{}/sync", slug)
+    }
+
+    /// The name of the checkpoint of the last time a digest was posted.
+    pub fn digest_name(slug: &str, digest: &str) -> String {
+        format!("{}/digest/{}", slug, digest)
+    }
+}
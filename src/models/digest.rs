@@ -0,0 +1,149 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+
+use crate::models::{domain_length::DomainLength, registered_domain::RegisteredDomain};
+
+/// The amount of names listed as the rarest of a digest.
+const RAREST_AMOUNT: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestPeriod {
+    Daily,
+    Weekly,
+}
+
+impl DigestPeriod {
+    pub fn duration(&self) -> Duration {
+        match self {
+            Self::Daily => Duration::days(1),
+            Self::Weekly => Duration::weeks(1),
+        }
+    }
+}
+
+impl std::fmt::Display for DigestPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Daily => write!(f, "daily"),
+            Self::Weekly => write!(f, "weekly"),
+        }
+    }
+}
+
+/// A summary of the registrations within a period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    pub period: DigestPeriod,
+    pub until: DateTime<Utc>,
+    /// The amount of registrations within the period.
+    pub registrations: usize,
+    /// The amount of registrations within the period per length tier, including
+    /// tiers without registrations.
+    pub by_length: Vec<(DomainLength, usize)>,
+    /// The rarest registered names, the shortest first.
+    pub rarest: Vec<String>,
+    /// The amount of domains registered since the launch.
+    pub total: i64,
+}
+
+impl Digest {
+    /// Summarizes the domains registered within the period ending at `until`.
+    ///
+    /// The domains must be within the period, valid, see
+    /// [`RegisteredDomain::validate`], and not counted by an earlier digest.
+    pub fn new(
+        period: DigestPeriod,
+        until: DateTime<Utc>,
+        domains: &[RegisteredDomain],
+        total: i64,
+    ) -> Self {
+        let mut domains = domains.iter().collect::<Vec<_>>();
+
+        domains.sort_by(|a, b| {
+            (a.domain.chars().count(), &a.domain).cmp(&(b.domain.chars().count(), &b.domain))
+        });
+
+        let by_length = DomainLength::ALL
+            .into_iter()
+            .map(|length| {
+                (
+                    length,
+                    domains.iter().filter(|d| d.length() == length).count(),
+                )
+            })
+            .collect();
+
+        Self {
+            period,
+            until,
+            registrations: domains.len(),
+            by_length,
+            rarest: domains
+                .iter()
+                .take(RAREST_AMOUNT)
+                .map(|domain| domain.domain.clone())
+                .collect(),
+            total,
+        }
+    }
+
+    pub fn since(&self) -> DateTime<Utc> {
+        self.until - self.period.duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn digest_new() {
+        let until = Utc.timestamp_opt(1698000000, 0).unwrap();
+        let domain = |name: &str, hours_ago: i64| {
+            RegisteredDomain::new(
+                name.to_string(),
+                "tx".to_string(),
+                until - Duration::hours(hours_ago),
+            )
+        };
+
+        let domains = vec![
+            domain("xiler.o", 1),
+            domain("abc.o", 2),
+            domain("x.o", 3),
+            domain("xi.o", 4),
+            domain("domains.o", 5),
+            domain("ordinals.o", 6),
+        ];
+
+        let digest = Digest::new(DigestPeriod::Daily, until, &domains, 100);
+
+        assert_eq!(digest.registrations, 6);
+        assert_eq!(
+            digest.by_length,
+            vec![
+                (DomainLength::SingleCharacter, 1),
+                (DomainLength::VeryShort, 1),
+                (DomainLength::Short, 1),
+                (DomainLength::Normal, 3),
+            ]
+        );
+        assert_eq!(
+            digest.rarest,
+            vec!["x.o", "xi.o", "abc.o", "xiler.o", "domains.o"]
+        );
+        assert_eq!(digest.total, 100);
+        assert_eq!(digest.since(), until - Duration::days(1));
+    }
+
+    #[test]
+    fn digest_weekly_since() {
+        let until = Utc.timestamp_opt(1698000000, 0).unwrap();
+        let digest = Digest::new(DigestPeriod::Weekly, until, &[], 1);
+
+        assert_eq!(digest.registrations, 0);
+        assert_eq!(digest.since(), until - Duration::weeks(1));
+    }
+}
@@ -1,4 +1,5 @@
-#[derive(Debug, Clone)]
+/// The length tier of a domain, ordered from the rarest to the most common tier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum DomainLength {
     SingleCharacter,
     VeryShort,
@@ -6,6 +7,15 @@ pub enum DomainLength {
     Normal,
 }
 
+impl DomainLength {
+    pub const ALL: [Self; 4] = [
+        Self::SingleCharacter,
+        Self::VeryShort,
+        Self::Short,
+        Self::Normal,
+    ];
+}
+
 impl From<&str> for DomainLength {
     /// Returns the domain length of a given domain.
     ///
@@ -52,13 +62,13 @@ impl From<String> for DomainLength {
     }
 }
 
-impl ToString for DomainLength {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for DomainLength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::SingleCharacter => "SingleCharacter".to_string(),
-            Self::VeryShort => "VeryShort".to_string(),
-            Self::Short => "Short".to_string(),
-            Self::Normal => "Normal".to_string(),
+            Self::SingleCharacter => write!(f, "SingleCharacter"),
+            Self::VeryShort => write!(f, "VeryShort"),
+            Self::Short => write!(f, "Short"),
+            Self::Normal => write!(f, "Normal"),
         }
     }
 }
@@ -1,5 +1,7 @@
 use serde::Serialize;
 
+use crate::utils::secret::Secret;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Inscription {
     id: String,
@@ -22,7 +24,7 @@ pub struct InscriptionMetaAttribute {
 pub struct NewInscriptions {
     pub new_inscriptions: Vec<Inscription>,
     creator_address: String,
-    creator_signature: String,
+    creator_signature: Secret<String>,
     slug: String,
 }
 
@@ -50,7 +52,7 @@ impl NewInscriptions {
     pub fn new(
         new_inscriptions: Vec<Inscription>,
         creator_address: String,
-        creator_signature: String,
+        creator_signature: Secret<String>,
         slug: String,
     ) -> Self {
         Self {
@@ -160,13 +162,16 @@ mod tests {
                 )],
             )],
             "creator_address".to_string(),
-            "creator_signature".to_string(),
+            "creator_signature".to_string().into(),
             "xiler-dns".to_string(),
         );
 
         assert_eq!(new_inscriptions.new_inscriptions.len(), 1);
         assert_eq!(new_inscriptions.creator_address, "creator_address");
-        assert_eq!(new_inscriptions.creator_signature, "creator_signature");
+        assert_eq!(
+            new_inscriptions.creator_signature.expose(),
+            "creator_signature"
+        );
         assert_eq!(new_inscriptions.slug, "xiler-dns");
     }
 
@@ -182,7 +187,7 @@ mod tests {
                 )],
             )],
             "creator_address".to_string(),
-            "creator_signature".to_string(),
+            "creator_signature".to_string().into(),
             "xiler-dns".to_string(),
         );
 
@@ -193,4 +198,20 @@ mod tests {
             r#"{"new_inscriptions":[{"id":"bd3bfa98c592fdb6ee81d4655082c43f27b63b05c706bd47bac4e1b715eab7a6i0","meta":{"name":"domain","attributes":[{"trait_type":"trait_type","value":"value"}]}}],"creator_address":"creator_address","creator_signature":"creator_signature","slug":"xiler-dns"}"#
         );
     }
+
+    #[test]
+    fn new_inscriptions_debug_redacts_signature() {
+        let new_inscriptions = NewInscriptions::new(
+            vec![],
+            "creator_address".to_string(),
+            "creator_signature".to_string().into(),
+            "xiler-dns".to_string(),
+        );
+
+        let debug = format!("{:?}", new_inscriptions);
+
+        assert!(!debug.contains("creator_signature\""));
+        assert!(debug.contains("creator_signature: ***"));
+        assert!(debug.contains("creator_address"));
+    }
 }
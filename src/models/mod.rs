@@ -1,2 +1,8 @@
+pub mod audit_entry;
+pub mod checkpoint;
+pub mod digest;
 pub mod domain_length;
 pub mod inscription;
+pub mod outbox_message;
+pub mod quarantined_domain;
+pub mod registered_domain;
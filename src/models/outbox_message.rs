@@ -0,0 +1,33 @@
+use crate::models::registered_domain::RegisteredDomain;
+
+/// The delivery status of an outbox message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxStatus {
+    /// Waiting for its (next) delivery attempt.
+    Pending,
+    Delivered,
+    /// Gave up after the maximum amount of attempts.
+    Failed,
+}
+
+impl std::fmt::Display for OutboxStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pending => write!(f, "pending"),
+            Self::Delivered => write!(f, "delivered"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// The announcement of a domain by a single notifier, kept in the outbox until it
+/// has been delivered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxMessage {
+    pub id: i64,
+    /// The key of the notifier, see [`crate::notifiers::Routes::assign`].
+    pub notifier: String,
+    pub domain: RegisteredDomain,
+    /// The amount of failed delivery attempts.
+    pub attempts: i32,
+}
@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+
+use crate::models::registered_domain::RegisteredDomain;
+
+/// A malformed domain that is not published until it is released.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedDomain {
+    pub domain: RegisteredDomain,
+    pub reason: String,
+    pub quarantined_at: DateTime<Utc>,
+    /// The time an operator released the domain, it is then published on the next
+    /// cycle if its registration was corrected.
+    pub released_at: Option<DateTime<Utc>>,
+}
+
+impl std::fmt::Display for QuarantinedDomain {
+    /// Formats the domain as a single line for the `quarantine` command.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}  {:?}  {}  {}",
+            self.quarantined_at
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            self.domain.domain,
+            self.domain.inscription_id,
+            self.reason,
+        )?;
+
+        if let Some(released_at) = self.released_at {
+            write!(
+                f,
+                "  (released at {})",
+                released_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::models::{domain_length::DomainLength, inscription::InscriptionMetaAttribute};
+
+/// A domain that has been registered and inscribed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredDomain {
+    pub domain: String,
+    pub reveal_tx: String,
+    pub inscription_id: String,
+    /// The time the payment of the registration was last updated. The registration
+    /// database has no registration or inscription time, so a later update of the
+    /// payment moves this time.
+    pub registered_at: DateTime<Utc>,
+}
+
+impl RegisteredDomain {
+    /// The names of the traits returned by [`RegisteredDomain::traits`].
+    pub const TRAITS: &'static [&'static str] = &["length", "charset"];
+
+    /// Creates a registered domain from the domain and its reveal transaction.
+    ///
+    /// The domain is inscribed in the first output of the reveal transaction, so the
+    /// inscription id is the reveal transaction id suffixed with `i0`.
+    pub fn new(domain: String, reveal_tx: String, registered_at: DateTime<Utc>) -> Self {
+        let inscription_id = format!("{}i0", reveal_tx);
+
+        Self {
+            domain,
+            reveal_tx,
+            inscription_id,
+            registered_at,
+        }
+    }
+
+    /// Checks that the domain is a `.o` domain with a name, malformed domains are
+    /// quarantined instead of published.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.domain.to_lowercase().strip_suffix(".o") {
+            None => Err("the domain does not end with .o".to_string()),
+            Some("") => Err("the domain has no name".to_string()),
+            Some(name) if name.chars().any(|c| c.is_whitespace() || c == '.') => {
+                Err("the name contains whitespace or dots".to_string())
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    pub fn length(&self) -> DomainLength {
+        DomainLength::from(self.domain.as_str())
+    }
+
+    /// Returns the traits of the domain, as published in the inscription metadata.
+    pub fn attributes(&self) -> Vec<InscriptionMetaAttribute> {
+        vec![InscriptionMetaAttribute::new(
+            "length".to_string(),
+            self.length().to_string(),
+        )]
+    }
+
+    /// Returns the attributes of the domain together with the traits computed from it,
+    /// used to pick the templates and routes of its announcements.
+    ///
+    /// * `length` - The [`DomainLength`] of the domain.
+    /// * `charset` - `Numeric`, `Alphabetic`, `Alphanumeric` or `Mixed`.
+    pub fn traits(&self) -> HashMap<String, String> {
+        let mut traits: HashMap<String, String> = self
+            .attributes()
+            .into_iter()
+            .map(|attribute| (attribute.trait_type, attribute.value))
+            .collect();
+
+        traits.insert("charset".to_string(), self.charset().to_string());
+
+        traits
+    }
+
+    fn charset(&self) -> &'static str {
+        let name = self.domain.trim_end_matches(".o");
+
+        if name.chars().all(|c| c.is_ascii_digit()) {
+            "Numeric"
+        } else if name.chars().all(|c| c.is_ascii_alphabetic()) {
+            "Alphabetic"
+        } else if name.chars().all(|c| c.is_ascii_alphanumeric()) {
+            "Alphanumeric"
+        } else {
+            "Mixed"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REVEAL_TX: &str = "bd3bfa98c592fdb6ee81d4655082c43f27b63b05c706bd47bac4e1b715eab7a6";
+
+    #[test]
+    fn registered_domain_new() {
+        let registered_at = Utc::now();
+        let domain =
+            RegisteredDomain::new("xiler.o".to_string(), REVEAL_TX.to_string(), registered_at);
+
+        assert_eq!(domain.domain, "xiler.o");
+        assert_eq!(domain.reveal_tx, REVEAL_TX);
+        assert_eq!(domain.inscription_id, format!("{}i0", REVEAL_TX));
+        assert_eq!(domain.registered_at, registered_at);
+    }
+
+    #[test]
+    fn registered_domain_validate() {
+        let validate = |name: &str| {
+            RegisteredDomain::new(name.to_string(), REVEAL_TX.to_string(), Utc::now()).validate()
+        };
+
+        assert!(validate("xiler.o").is_ok());
+        assert!(validate("xiler").is_err());
+        assert!(validate(".o").is_err());
+        assert!(validate("xi ler.o").is_err());
+        assert!(validate("sub.xiler.o").is_err());
+    }
+
+    #[test]
+    fn registered_domain_length() {
+        let domain = RegisteredDomain::new("x.o".to_string(), REVEAL_TX.to_string(), Utc::now());
+
+        assert!(matches!(domain.length(), DomainLength::SingleCharacter));
+    }
+
+    #[test]
+    fn registered_domain_attributes() {
+        let domain = RegisteredDomain::new("xi.o".to_string(), REVEAL_TX.to_string(), Utc::now());
+        let attributes = domain.attributes();
+
+        assert_eq!(attributes.len(), 1);
+        assert_eq!(attributes[0].trait_type, "length");
+        assert_eq!(attributes[0].value, "VeryShort");
+    }
+
+    #[test]
+    fn registered_domain_traits() {
+        let charset = |name: &str| {
+            RegisteredDomain::new(name.to_string(), REVEAL_TX.to_string(), Utc::now()).traits()
+                ["charset"]
+                .clone()
+        };
+
+        assert_eq!(charset("420.o"), "Numeric");
+        assert_eq!(charset("xiler.o"), "Alphabetic");
+        assert_eq!(charset("x1ler.o"), "Alphanumeric");
+        assert_eq!(charset("xi-ler.o"), "Mixed");
+
+        let domain = RegisteredDomain::new("x.o".to_string(), REVEAL_TX.to_string(), Utc::now());
+        let mut traits = domain.traits().into_keys().collect::<Vec<_>>();
+        traits.sort();
+
+        assert_eq!(traits, vec!["charset", "length"]);
+    }
+}
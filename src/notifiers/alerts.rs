@@ -0,0 +1,310 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use tracing::{error, info, warn};
+
+use crate::{
+    models::registered_domain::RegisteredDomain,
+    notifiers::{DiscordNotifier, EmailNotifier},
+};
+
+const ALERT_COLOR: u32 = 15548997;
+const RECOVERY_COLOR: u32 = 5763719;
+
+/// A condition in which the sync is unhealthy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertCondition {
+    DatabaseUnreachable,
+    PublishingFailing,
+    /// Domains are pending, but the last update timestamp does not advance.
+    CheckpointStalled,
+}
+
+impl std::fmt::Display for AlertCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DatabaseUnreachable => write!(f, "Database unreachable"),
+            Self::PublishingFailing => write!(f, "Publishing inscriptions fails"),
+            Self::CheckpointStalled => write!(f, "Last update is not advancing"),
+        }
+    }
+}
+
+/// A message for the ops channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlertEvent {
+    Alert {
+        condition: AlertCondition,
+        since: DateTime<Utc>,
+        detail: String,
+    },
+    Recovery {
+        condition: AlertCondition,
+        since: DateTime<Utc>,
+    },
+    Quarantined {
+        domain: String,
+        reason: String,
+    },
+}
+
+impl AlertEvent {
+    pub fn title(&self) -> String {
+        match self {
+            Self::Alert { condition, .. } => format!("Alert: {}", condition),
+            Self::Recovery { condition, .. } => format!("Recovered: {}", condition),
+            Self::Quarantined { .. } => "Domain quarantined".to_string(),
+        }
+    }
+
+    /// Returns a plain text description of the event.
+    pub fn text(&self) -> String {
+        match self {
+            Self::Alert { since, detail, .. } => {
+                format!("Since {}:\n{}", since.to_rfc3339(), detail)
+            }
+            Self::Recovery { since, .. } => format!(
+                "The condition started at {} and has cleared.",
+                since.to_rfc3339()
+            ),
+            Self::Quarantined { domain, reason } => {
+                format!("{} is not published: {}", domain, reason)
+            }
+        }
+    }
+}
+
+/// Tracks the alert conditions, so an alert is only raised once a condition lasts
+/// longer than the threshold and only once until it has recovered.
+#[derive(Debug)]
+pub struct AlertState {
+    threshold: Duration,
+    /// The time since which every condition has been failing.
+    failing_since: HashMap<AlertCondition, DateTime<Utc>>,
+    /// The conditions for which an alert was raised.
+    raised: HashSet<AlertCondition>,
+}
+
+impl AlertState {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            failing_since: HashMap::new(),
+            raised: HashSet::new(),
+        }
+    }
+
+    /// Records that the condition occurred, returns an alert when it has lasted for
+    /// the threshold and no alert was raised yet.
+    pub fn failing(
+        &mut self,
+        condition: AlertCondition,
+        detail: &str,
+        now: DateTime<Utc>,
+    ) -> Option<AlertEvent> {
+        let since = *self.failing_since.entry(condition).or_insert(now);
+
+        if now - since < self.threshold || self.raised.contains(&condition) {
+            return None;
+        }
+
+        self.raised.insert(condition);
+
+        Some(AlertEvent::Alert {
+            condition,
+            since,
+            detail: detail.to_string(),
+        })
+    }
+
+    /// Records that the condition cleared, returns a recovery if an alert was raised.
+    pub fn recovered(&mut self, condition: AlertCondition) -> Option<AlertEvent> {
+        let since = self.failing_since.remove(&condition)?;
+
+        self.raised
+            .remove(&condition)
+            .then_some(AlertEvent::Recovery { condition, since })
+    }
+}
+
+/// Sends operational alerts to the ops webhook and the failure recipients of the
+/// email notifiers, separate from the announcements.
+///
+/// Without either, alerts are only logged.
+pub struct Alerter {
+    webhook: Option<DiscordNotifier>,
+    emails: Vec<EmailNotifier>,
+    state: AlertState,
+}
+
+impl Alerter {
+    pub fn new(
+        webhook: Option<DiscordNotifier>,
+        emails: Vec<EmailNotifier>,
+        threshold: Duration,
+    ) -> Self {
+        Self {
+            webhook,
+            emails: emails
+                .into_iter()
+                .filter(EmailNotifier::receives_failures)
+                .collect(),
+            state: AlertState::new(threshold),
+        }
+    }
+
+    pub async fn failing(&mut self, condition: AlertCondition, detail: &str) {
+        if let Some(event) = self.state.failing(condition, detail, Utc::now()) {
+            self.send(event).await;
+        }
+    }
+
+    pub async fn recovered(&mut self, condition: AlertCondition) {
+        if let Some(event) = self.state.recovered(condition) {
+            self.send(event).await;
+        }
+    }
+
+    /// Alerts that the domain was quarantined, the quarantine in the database makes
+    /// sure this happens once per domain.
+    pub async fn quarantined(&self, domain: &RegisteredDomain, reason: &str) {
+        self.send(AlertEvent::Quarantined {
+            domain: domain.domain.clone(),
+            reason: reason.to_string(),
+        })
+        .await;
+    }
+
+    async fn send(&self, event: AlertEvent) {
+        match &event {
+            AlertEvent::Recovery { condition, .. } => info!("[ALERTS] Recovered: {}", condition),
+            _ => warn!("[ALERTS] {:?}", event),
+        }
+
+        if let Some(webhook) = &self.webhook {
+            if let Err(e) = webhook.send_embeds(vec![embed(&event)]).await {
+                error!("[ALERTS] Error sending alert: {:?}", e);
+            }
+        }
+
+        for email in &self.emails {
+            if let Err(e) = email.send_alert(&event).await {
+                error!("[ALERTS] Error sending alert email: {}", e);
+            }
+        }
+    }
+}
+
+fn embed(event: &AlertEvent) -> serde_json::Value {
+    match event {
+        AlertEvent::Alert { since, detail, .. } => json!({
+            "title": event.title(),
+            "description": format!("Since <t:{}:R>\n```\n{}\n```", since.timestamp(), detail),
+            "color": ALERT_COLOR
+        }),
+        AlertEvent::Recovery { since, .. } => json!({
+            "title": event.title(),
+            "description": format!("The condition started <t:{}:R> and has cleared.", since.timestamp()),
+            "color": RECOVERY_COLOR
+        }),
+        AlertEvent::Quarantined { domain, reason } => json!({
+            "title": event.title(),
+            "description": format!("`{}` is not published: {}", domain, reason),
+            "color": ALERT_COLOR
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifiers::test_domains;
+    use mockito::Matcher;
+
+    fn state() -> AlertState {
+        AlertState::new(Duration::minutes(5))
+    }
+
+    #[test]
+    fn alert_state_raises_after_threshold() {
+        let mut state = state();
+        let start = Utc::now();
+        let condition = AlertCondition::DatabaseUnreachable;
+
+        assert_eq!(state.failing(condition, "timeout", start), None);
+        assert_eq!(
+            state.failing(condition, "timeout", start + Duration::minutes(4)),
+            None
+        );
+        assert_eq!(
+            state.failing(condition, "timeout", start + Duration::minutes(5)),
+            Some(AlertEvent::Alert {
+                condition,
+                since: start,
+                detail: "timeout".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn alert_state_deduplicates() {
+        let mut state = state();
+        let start = Utc::now();
+        let condition = AlertCondition::PublishingFailing;
+
+        state.failing(condition, "500", start);
+        assert!(state
+            .failing(condition, "500", start + Duration::minutes(5))
+            .is_some());
+        assert!(state
+            .failing(condition, "500", start + Duration::minutes(10))
+            .is_none());
+    }
+
+    #[test]
+    fn alert_state_recovers() {
+        let mut state = state();
+        let start = Utc::now();
+        let condition = AlertCondition::CheckpointStalled;
+
+        // Recovering before the threshold does not send anything.
+        state.failing(condition, "", start);
+        assert_eq!(state.recovered(condition), None);
+
+        state.failing(condition, "", start);
+        state.failing(condition, "", start + Duration::minutes(5));
+        assert_eq!(
+            state.recovered(condition),
+            Some(AlertEvent::Recovery {
+                condition,
+                since: start
+            })
+        );
+        assert_eq!(state.recovered(condition), None);
+    }
+
+    #[tokio::test]
+    async fn alerter_sends_to_ops_webhook() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "embeds": [{ "title": "Domain quarantined" }]
+            })))
+            .with_status(204)
+            .expect(1)
+            .create();
+
+        let alerter = Alerter::new(
+            Some(DiscordNotifier::new(server.url().into())),
+            vec![],
+            Duration::minutes(5),
+        );
+        let domain = test_domains(1).remove(0);
+
+        alerter.quarantined(&domain, "invalid").await;
+
+        mock.assert();
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::models::registered_domain::RegisteredDomain;
+
+/// One or more accepted values of a trait.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum TraitValues {
+    One(String),
+    Any(Vec<String>),
+}
+
+impl TraitValues {
+    fn contains(&self, value: &str) -> bool {
+        match self {
+            Self::One(expected) => expected == value,
+            Self::Any(expected) => expected.iter().any(|expected| expected == value),
+        }
+    }
+}
+
+/// The traits a domain must have, e.g. `{ length = ["SingleCharacter", "VeryShort"] }`.
+///
+/// Every listed trait must match one of its values, so empty conditions match
+/// every domain.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Conditions(HashMap<String, TraitValues>);
+
+impl Conditions {
+    pub fn matches(&self, domain: &RegisteredDomain) -> bool {
+        let traits = domain.traits();
+
+        self.0.iter().all(|(trait_type, values)| {
+            traits
+                .get(trait_type)
+                .is_some_and(|value| values.contains(value))
+        })
+    }
+
+    /// Checks that only traits computed by [`RegisteredDomain::traits`] are used.
+    pub fn validate(&self) -> Result<(), String> {
+        match self
+            .0
+            .keys()
+            .find(|trait_type| !RegisteredDomain::TRAITS.contains(&trait_type.as_str()))
+        {
+            Some(trait_type) => Err(format!(
+                "Unknown trait `{}`, expected one of: {}",
+                trait_type,
+                RegisteredDomain::TRAITS.join(", ")
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Conditions {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), TraitValues::One(value.into())))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domain(name: &str) -> RegisteredDomain {
+        RegisteredDomain::new(name.to_string(), "tx".to_string(), chrono::Utc::now())
+    }
+
+    #[test]
+    fn conditions_match() {
+        let conditions: Conditions =
+            toml::from_str(r#"length = ["SingleCharacter", "VeryShort"]"#).unwrap();
+
+        assert!(conditions.matches(&domain("x.o")));
+        assert!(conditions.matches(&domain("xi.o")));
+        assert!(!conditions.matches(&domain("xiler.o")));
+        assert!(Conditions::default().matches(&domain("xiler.o")));
+    }
+
+    #[test]
+    fn conditions_match_all_traits() {
+        let conditions: Conditions = toml::from_str(
+            r#"length = "VeryShort"
+charset = "Numeric""#,
+        )
+        .unwrap();
+
+        assert!(conditions.matches(&domain("42.o")));
+        assert!(!conditions.matches(&domain("xi.o")));
+    }
+
+    #[test]
+    fn conditions_validate() {
+        let valid: Conditions = [("length", "Short")].into_iter().collect();
+        let invalid: Conditions = [("lenght", "Short")].into_iter().collect();
+
+        assert!(valid.validate().is_ok());
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::HashSet;
+
+use bitcoin::Network;
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc, Weekday};
+use color_eyre::eyre::Result;
+use serde::Deserialize;
+use tracing::{debug, error, info};
+
+use crate::{
+    db::{CheckpointRepository, DigestRepository, DomainsRepository},
+    models::{
+        checkpoint::Checkpoint,
+        digest::{Digest, DigestPeriod},
+    },
+    notifiers::{DiscordNotifier, Notifier},
+    utils::{secret::Secret, shutdown::Shutdown},
+};
+
+/// The amount of seconds between checks for due digests.
+const CHECK_INTERVAL: u64 = 60;
+
+/// A digest that is posted to a Discord webhook at a fixed time.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DigestSchedule {
+    /// The name under which the time of the last digest is kept in the database,
+    /// defaults to the period.
+    pub name: Option<String>,
+    pub period: DigestPeriod,
+    /// The UTC time of the day at which the digest is posted, e.g. `09:00`.
+    pub at: NaiveTime,
+    /// The day of the week at which a weekly digest is posted, defaults to Monday.
+    pub weekday: Option<Weekday>,
+    pub webhook_url: Secret<String>,
+}
+
+impl DigestSchedule {
+    /// Returns the most recent time at which the digest was due.
+    pub fn last_due(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let mut due = now.date_naive().and_time(self.at).and_utc();
+
+        if due > now {
+            due -= Duration::days(1);
+        }
+
+        if self.period == DigestPeriod::Weekly {
+            let weekday = self.weekday.unwrap_or(Weekday::Mon);
+            let days_back =
+                (due.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+
+            due -= Duration::days(days_back as i64);
+        }
+
+        due
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.period.to_string())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.period == DigestPeriod::Daily && self.weekday.is_some() {
+            return Err("Digest weekday can only be set for weekly digests".to_string());
+        }
+
+        DiscordNotifier::new(self.webhook_url.clone()).validate()
+    }
+}
+
+/// Posts the digests when they are due, checking every minute and once more when
+/// the shutdown is requested.
+pub async fn run_digest_scheduler<
+    R: DomainsRepository + CheckpointRepository + DigestRepository,
+>(
+    repository: R,
+    digests: Vec<DigestSchedule>,
+    network: Network,
+    slug: String,
+    mut shutdown: Shutdown,
+) {
+    if digests.is_empty() {
+        return;
+    }
+
+    info!("[DIGEST] Started scheduler for {} digests", digests.len());
+
+    loop {
+        for digest in &digests {
+            if let Err(e) = send_if_due(&repository, digest, network, &slug, Utc::now()).await {
+                error!("[DIGEST] Error sending {} digest: {:?}", digest.name(), e);
+            }
+        }
+
+        if !shutdown.sleep(CHECK_INTERVAL).await {
+            break;
+        }
+    }
+
+    info!("[DIGEST] Stopped scheduler");
+}
+
+/// Posts the digest if it has not been posted since it was last due, and returns
+/// whether it was posted.
+///
+/// The time of the last digest is kept in the database, so a new leader does not
+/// post it again. The first time a digest is checked only this time is set, so a
+/// new digest is first posted at its next scheduled time.
+///
+/// Every domain is counted once, by the first digest of its period. A payment that
+/// is updated later moves the registration time into a later period, the domains
+/// counted by a digest are kept in the database to leave them out then.
+async fn send_if_due<R: DomainsRepository + CheckpointRepository + DigestRepository>(
+    repository: &R,
+    schedule: &DigestSchedule,
+    network: Network,
+    slug: &str,
+    now: DateTime<Utc>,
+) -> Result<bool> {
+    let name = Checkpoint::digest_name(slug, &schedule.name());
+    let Some(last_sent) = repository.get_checkpoint(&name).await? else {
+        debug!("[DIGEST] Scheduling first {} digest", schedule.name());
+        repository
+            .set_checkpoint(&Checkpoint::new(name, now))
+            .await?;
+        return Ok(false);
+    };
+
+    let due = schedule.last_due(now);
+    if last_sent >= due {
+        return Ok(false);
+    }
+
+    let since = due - schedule.period.duration();
+    let mut domains = repository
+        .get_domains_between(since.naive_utc(), due.naive_utc())
+        .await?
+        .into_iter()
+        .filter(|domain| match domain.validate() {
+            Ok(()) => true,
+            Err(reason) => {
+                // The sync loop quarantines and alerts about these.
+                debug!(
+                    "[DIGEST] Leaving malformed domain {:?} out: {}",
+                    domain.domain, reason
+                );
+                false
+            }
+        })
+        .collect::<Vec<_>>();
+    let reveal_txs = domains
+        .iter()
+        .map(|domain| domain.reveal_tx.clone())
+        .collect::<Vec<_>>();
+    let mut counted = repository
+        .get_counted_domains(&name, &reveal_txs)
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    // Leaves out the domains counted before and counts every domain once.
+    domains.retain(|domain| counted.insert(domain.reveal_tx.clone()));
+
+    let total = repository.count_domains().await?;
+    let digest = Digest::new(schedule.period, due, &domains, total);
+
+    DiscordNotifier::new(schedule.webhook_url.clone())
+        .with_network(network)
+        .send_digest(&digest)
+        .await?;
+
+    let reveal_txs = domains
+        .into_iter()
+        .map(|domain| domain.reveal_tx)
+        .collect::<Vec<_>>();
+    repository
+        .record_digest(&reveal_txs, &Checkpoint::new(name, due))
+        .await?;
+    info!(
+        "[DIGEST] Sent {} digest of {} registrations",
+        schedule.name(),
+        digest.registrations
+    );
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+    use crate::{
+        db::repository::memory::MemoryRepository, models::registered_domain::RegisteredDomain,
+        notifiers::test_domains,
+    };
+
+    fn schedule(period: DigestPeriod, webhook_url: String) -> DigestSchedule {
+        DigestSchedule {
+            name: None,
+            period,
+            at: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            weekday: None,
+            webhook_url: webhook_url.into(),
+        }
+    }
+
+    fn time(s: &str) -> DateTime<Utc> {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M")
+            .unwrap()
+            .and_utc()
+    }
+
+    #[test]
+    fn digest_schedule_from_toml() {
+        let schedule: DigestSchedule = toml::from_str(
+            r#"
+                period = "weekly"
+                at = "09:30"
+                weekday = "fri"
+                webhook_url = "https://discord.com/api/webhooks/1234/token"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(schedule.at, NaiveTime::from_hms_opt(9, 30, 0).unwrap());
+        assert_eq!(schedule.weekday, Some(Weekday::Fri));
+        assert_eq!(schedule.name(), "weekly");
+        assert!(schedule.validate().is_ok());
+    }
+
+    #[test]
+    fn digest_schedule_last_due_daily() {
+        let schedule = schedule(DigestPeriod::Daily, String::new());
+
+        // 2023-10-25 is a Wednesday.
+        assert_eq!(
+            schedule.last_due(time("2023-10-25 10:00")),
+            time("2023-10-25 09:00")
+        );
+        assert_eq!(
+            schedule.last_due(time("2023-10-25 08:59")),
+            time("2023-10-24 09:00")
+        );
+        assert_eq!(
+            schedule.last_due(time("2023-10-25 09:00")),
+            time("2023-10-25 09:00")
+        );
+    }
+
+    #[test]
+    fn digest_schedule_last_due_weekly() {
+        let mut schedule = schedule(DigestPeriod::Weekly, String::new());
+
+        assert_eq!(
+            schedule.last_due(time("2023-10-25 10:00")),
+            time("2023-10-23 09:00")
+        );
+        assert_eq!(
+            schedule.last_due(time("2023-10-30 08:00")),
+            time("2023-10-23 09:00")
+        );
+
+        schedule.weekday = Some(Weekday::Wed);
+        assert_eq!(
+            schedule.last_due(time("2023-10-25 10:00")),
+            time("2023-10-25 09:00")
+        );
+        assert_eq!(
+            schedule.last_due(time("2023-10-25 08:00")),
+            time("2023-10-18 09:00")
+        );
+    }
+
+    #[test]
+    fn digest_schedule_validate_weekday() {
+        let mut schedule = schedule(
+            DigestPeriod::Daily,
+            "https://discord.com/api/webhooks/1234/token".to_string(),
+        );
+        assert!(schedule.validate().is_ok());
+
+        schedule.weekday = Some(Weekday::Mon);
+        assert!(schedule.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn send_if_due_posts_once() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/").with_status(204).expect(1).create();
+
+        let schedule = schedule(DigestPeriod::Daily, server.url());
+        let repository = MemoryRepository::new(test_domains(2));
+        let now = time("2023-10-25 10:00");
+
+        // The first check only schedules the digest.
+        assert!(!send_if_due(
+            &repository,
+            &schedule,
+            Network::Bitcoin,
+            "test",
+            time("2023-10-24 12:00")
+        )
+        .await
+        .unwrap());
+        assert!(
+            send_if_due(&repository, &schedule, Network::Bitcoin, "test", now)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !send_if_due(&repository, &schedule, Network::Bitcoin, "test", now)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            repository
+                .get_checkpoint("test/digest/daily")
+                .await
+                .unwrap(),
+            Some(time("2023-10-25 09:00"))
+        );
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn send_if_due_counts_valid_domains_of_the_period() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(
+                "2 domains have been registered in the last 24 hours".to_string(),
+            ))
+            .with_status(204)
+            .expect(1)
+            .create();
+
+        let domain = |name: &str, at: &str| {
+            RegisteredDomain::new(name.to_string(), format!("{}tx", name), time(at))
+        };
+        let repository = MemoryRepository::new(vec![
+            domain("before.o", "2023-10-24 09:00"),
+            domain("first.o", "2023-10-24 09:01"),
+            domain("first.o", "2023-10-24 10:00"),
+            domain("second.o", "2023-10-25 09:00"),
+            domain("malformed", "2023-10-25 08:00"),
+            domain("after.o", "2023-10-25 09:30"),
+        ]);
+        repository
+            .set_checkpoint(&Checkpoint::new(
+                "test/digest/daily".to_string(),
+                time("2023-10-24 09:00"),
+            ))
+            .await
+            .unwrap();
+
+        let schedule = schedule(DigestPeriod::Daily, server.url());
+        assert!(send_if_due(
+            &repository,
+            &schedule,
+            Network::Bitcoin,
+            "test",
+            time("2023-10-25 10:00")
+        )
+        .await
+        .unwrap());
+
+        mock.assert();
+    }
+
+    // The registration time is the last update of the payment, which moves the
+    // domain into the next period.
+    #[tokio::test]
+    async fn send_if_due_counts_a_domain_once_after_a_payment_update() {
+        let mut server = mockito::Server::new();
+        let first = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(
+                "1 domain has been registered in the last 24 hours".to_string(),
+            ))
+            .with_status(204)
+            .expect(1)
+            .create();
+        let second = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(
+                "0 domains have been registered in the last 24 hours".to_string(),
+            ))
+            .with_status(204)
+            .expect(1)
+            .create();
+
+        let repository = MemoryRepository::new(vec![RegisteredDomain::new(
+            "xiler.o".to_string(),
+            "tx".to_string(),
+            time("2023-10-24 12:00"),
+        )]);
+        repository
+            .set_checkpoint(&Checkpoint::new(
+                "test/digest/daily".to_string(),
+                time("2023-10-24 09:00"),
+            ))
+            .await
+            .unwrap();
+        let schedule = schedule(DigestPeriod::Daily, server.url());
+
+        assert!(send_if_due(
+            &repository,
+            &schedule,
+            Network::Bitcoin,
+            "test",
+            time("2023-10-25 10:00")
+        )
+        .await
+        .unwrap());
+
+        repository.domains.lock().unwrap()[0].registered_at = time("2023-10-25 12:00");
+        assert!(send_if_due(
+            &repository,
+            &schedule,
+            Network::Bitcoin,
+            "test",
+            time("2023-10-26 10:00")
+        )
+        .await
+        .unwrap());
+
+        first.assert();
+        second.assert();
+    }
+}
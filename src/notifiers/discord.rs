@@ -0,0 +1,519 @@
+use std::path::PathBuf;
+
+use bitcoin::Network;
+use reqwest::{
+    multipart::{Form, Part},
+    Url,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::error;
+
+use crate::{
+    models::{
+        audit_entry::Receipt,
+        digest::{Digest, DigestPeriod},
+        registered_domain::RegisteredDomain,
+    },
+    notifiers::{
+        explorers::Explorers,
+        mainnet, network_tag, send,
+        templates::{Announcement, Templates, DEFAULT_COLOR},
+        Notifier, NotifyError,
+    },
+    utils::{preview::PreviewRenderer, secret::Secret},
+};
+
+static DISCORD_WEBHOOK_HOSTS: &[&str] = &[
+    "discord.com",
+    "discordapp.com",
+    "ptb.discord.com",
+    "canary.discord.com",
+];
+
+/// Announces domains through a Discord webhook, one embed per domain.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordNotifier {
+    webhook_url: Secret<String>,
+    /// The announcement templates of this notifier, overrides `templates_file` of
+    /// the configuration.
+    templates_file: Option<PathBuf>,
+    #[serde(skip)]
+    templates: Templates,
+    /// Whether to attach a generated preview image to every announcement, overrides
+    /// `preview_images` of the configuration.
+    preview_images: Option<bool>,
+    #[serde(skip)]
+    previews: Option<PreviewRenderer>,
+    #[serde(skip, default = "mainnet")]
+    network: Network,
+    #[serde(skip)]
+    explorers: Explorers,
+    #[serde(skip)]
+    client: reqwest::Client,
+}
+
+impl DiscordNotifier {
+    pub fn new(webhook_url: Secret<String>) -> Self {
+        Self {
+            webhook_url,
+            templates_file: None,
+            templates: Templates::default(),
+            preview_images: None,
+            previews: None,
+            network: Network::Bitcoin,
+            explorers: Explorers::default(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Tags the announcements with the network, unless it is mainnet.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Links the announcements to the given explorers.
+    pub fn with_explorers(mut self, explorers: Explorers) -> Self {
+        self.explorers = explorers;
+        self
+    }
+
+    pub fn with_templates(mut self, templates: Templates) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// Attaches a preview image rendered by `previews` to every announcement.
+    pub fn with_previews(mut self, previews: PreviewRenderer) -> Self {
+        self.previews = Some(previews);
+        self
+    }
+
+    pub fn templates_file(&self) -> Option<&PathBuf> {
+        self.templates_file.as_ref()
+    }
+
+    pub fn preview_images(&self) -> Option<bool> {
+        self.preview_images
+    }
+
+    /// Sends a single embed summarizing the digest.
+    pub async fn send_digest(&self, digest: &Digest) -> Result<Receipt, NotifyError> {
+        self.send_embeds(vec![digest_embed(digest)]).await
+    }
+
+    /// Sends a single message with the given embeds.
+    pub async fn send_embeds(
+        &self,
+        embeds: Vec<serde_json::Value>,
+    ) -> Result<Receipt, NotifyError> {
+        let embeds = self.tag_embeds(embeds);
+        let json = json!({
+            "content": null,
+            "embeds": embeds,
+            "attachments": []
+        });
+
+        send(self.client.post(self.webhook_url.expose()).json(&json)).await
+    }
+
+    /// Sends a single message with the given embeds and attached PNG images, which
+    /// embeds reference as `attachment://{file name}`.
+    pub async fn send_embeds_with_images(
+        &self,
+        embeds: Vec<serde_json::Value>,
+        images: Vec<(String, Vec<u8>)>,
+    ) -> Result<Receipt, NotifyError> {
+        let attachments = images
+            .iter()
+            .enumerate()
+            .map(|(id, (file_name, _))| json!({ "id": id, "filename": file_name }))
+            .collect::<Vec<_>>();
+
+        let embeds = self.tag_embeds(embeds);
+        let json = json!({
+            "content": null,
+            "embeds": embeds,
+            "attachments": attachments
+        });
+
+        let mut form = Form::new().text("payload_json", json.to_string());
+        for (id, (file_name, png)) in images.into_iter().enumerate() {
+            let part = Part::bytes(png)
+                .file_name(file_name)
+                .mime_str("image/png")?;
+            form = form.part(format!("files[{}]", id), part);
+        }
+
+        send(self.client.post(self.webhook_url.expose()).multipart(form)).await
+    }
+
+    /// Adds the network as footer to embeds without one, unless it is mainnet.
+    fn tag_embeds(&self, mut embeds: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+        if let Some(tag) = network_tag(self.network) {
+            for embed in &mut embeds {
+                if embed.get("footer").is_none() {
+                    embed["footer"] = json!({ "text": tag });
+                }
+            }
+        }
+
+        embeds
+    }
+
+    /// Renders the preview images of the domains, domains whose preview fails to
+    /// render are announced without one.
+    async fn render_previews(
+        previews: PreviewRenderer,
+        domains: Vec<String>,
+    ) -> Vec<Option<Vec<u8>>> {
+        tokio::task::spawn_blocking(move || {
+            domains
+                .iter()
+                .map(|domain| {
+                    previews
+                        .card(domain)
+                        .map_err(|e| {
+                            error!("[DISCORD] Failed to render preview of {}: {:?}", domain, e)
+                        })
+                        .ok()
+                })
+                .collect()
+        })
+        .await
+        .unwrap_or_else(|e| {
+            error!("[DISCORD] Failed to render previews: {:?}", e);
+            vec![]
+        })
+    }
+}
+
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "DISCORD"
+    }
+
+    /// Discord allows up to 10 embeds per message.
+    fn batch_size(&self) -> usize {
+        10
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match Url::parse(self.webhook_url.expose()) {
+            Ok(url) if is_discord_webhook(&url) => Ok(()),
+            Ok(_) => Err("Discord webhook_url is not a Discord webhook URL".to_string()),
+            Err(e) => Err(format!("Discord webhook_url is not a valid URL: {}", e)),
+        }
+    }
+
+    async fn notify(&self, domains: &[RegisteredDomain]) -> Result<Receipt, NotifyError> {
+        let mut embeds = domains
+            .iter()
+            .map(|domain| embed(self.templates.render(domain, &self.explorers)))
+            .collect::<Vec<serde_json::Value>>();
+
+        let Some(previews) = &self.previews else {
+            return self.send_embeds(embeds).await;
+        };
+
+        let names = domains.iter().map(|d| d.domain.clone()).collect();
+        let rendered = Self::render_previews(previews.clone(), names).await;
+
+        let mut images = vec![];
+        for (embed, png) in embeds.iter_mut().zip(rendered) {
+            if let Some(png) = png {
+                let file_name = format!("preview{}.png", images.len());
+                embed["image"] = json!({ "url": format!("attachment://{}", file_name) });
+                images.push((file_name, png));
+            }
+        }
+
+        self.send_embeds_with_images(embeds, images).await
+    }
+}
+
+fn embed(announcement: Announcement) -> serde_json::Value {
+    let mut embed = json!({
+        "description": announcement.description,
+        "color": announcement.color
+    });
+
+    if let Some(title) = announcement.title {
+        embed["title"] = json!(title);
+    }
+
+    if let Some(thumbnail) = announcement.thumbnail {
+        embed["thumbnail"] = json!({ "url": thumbnail });
+    }
+
+    if !announcement.fields.is_empty() {
+        embed["fields"] = json!(announcement.fields);
+    }
+
+    embed
+}
+
+fn digest_embed(digest: &Digest) -> serde_json::Value {
+    let (title, period) = match digest.period {
+        DigestPeriod::Daily => ("Daily digest", "24 hours"),
+        DigestPeriod::Weekly => ("Weekly digest", "7 days"),
+    };
+
+    let mut fields = digest
+        .by_length
+        .iter()
+        .map(|(length, amount)| {
+            json!({
+                "name": length.to_string(),
+                "value": amount.to_string(),
+                "inline": true
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if !digest.rarest.is_empty() {
+        fields.push(json!({
+            "name": "Rarest names",
+            "value": digest
+                .rarest
+                .iter()
+                .map(|domain| format!("`{}`", domain))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            "inline": false
+        }));
+    }
+
+    fields.push(json!({
+        "name": "Total registered",
+        "value": digest.total.to_string(),
+        "inline": false
+    }));
+
+    let description = match digest.registrations {
+        1 => format!("1 domain has been registered in the last {}.", period),
+        amount => format!(
+            "{} domains have been registered in the last {}.",
+            amount, period
+        ),
+    };
+
+    json!({
+        "title": title,
+        "description": description,
+        "color": DEFAULT_COLOR,
+        "fields": fields,
+        "timestamp": digest.until.to_rfc3339()
+    })
+}
+
+fn is_discord_webhook(url: &Url) -> bool {
+    url.scheme() == "https"
+        && url
+            .host_str()
+            .is_some_and(|host| DISCORD_WEBHOOK_HOSTS.contains(&host))
+        && url.path().starts_with("/api/webhooks/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifiers::test_domains;
+    use mockito::Matcher;
+
+    #[tokio::test]
+    async fn discord_notify() {
+        let mut server = mockito::Server::new();
+        let domains = test_domains(2);
+
+        let mock = server
+            .mock("POST", "/api/webhooks/1234/token")
+            .match_header("content-type", "application/json")
+            .match_body(Matcher::PartialJson(json!({
+                "embeds": [
+                    {
+                        "description": format!(
+                            "`domain0.o` has just been registered!\n[{0}](https://ordinalswallet.com/inscription/{0})",
+                            domains[0].inscription_id
+                        ),
+                        "color": 3512539
+                    },
+                    {
+                        "description": format!(
+                            "`domain1.o` has just been registered!\n[{0}](https://ordinalswallet.com/inscription/{0})",
+                            domains[1].inscription_id
+                        ),
+                        "color": 3512539
+                    }
+                ]
+            })))
+            .with_status(204)
+            .create();
+
+        let notifier =
+            DiscordNotifier::new(format!("{}/api/webhooks/1234/token", server.url()).into());
+
+        assert!(notifier.notify(&domains).await.is_ok());
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn discord_notify_with_templates() {
+        let mut server = mockito::Server::new();
+        let domains = test_domains(1);
+
+        let templates: Templates = Templates::new(vec![toml::from_str(
+            r#"
+                name = "all"
+                title = "{{domain}}"
+                description = "{{name}} registered"
+                color = 255
+                thumbnail = "https://xiler.net/{{name}}.png"
+                fields = [{ name = "Length", value = "{{length}}", inline = true }]
+            "#,
+        )
+        .unwrap()])
+        .unwrap();
+
+        let mock = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "embeds": [{
+                    "title": "domain0.o",
+                    "description": "domain0 registered",
+                    "color": 255,
+                    "thumbnail": { "url": "https://xiler.net/domain0.png" },
+                    "fields": [{ "name": "Length", "value": "Normal", "inline": true }]
+                }]
+            })))
+            .with_status(204)
+            .create();
+
+        let notifier = DiscordNotifier::new(server.url().into()).with_templates(templates);
+
+        assert!(notifier.notify(&domains).await.is_ok());
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn discord_notify_with_previews() {
+        let mut server = mockito::Server::new();
+        let domains = test_domains(2);
+
+        let mock = server
+            .mock("POST", "/")
+            .match_header(
+                "content-type",
+                Matcher::Regex("^multipart/form-data; boundary=".to_string()),
+            )
+            .match_body(Matcher::AllOf(vec![
+                Matcher::Regex(r#"name="payload_json""#.to_string()),
+                Matcher::Regex(r#""image":\{"url":"attachment://preview0.png"\}"#.to_string()),
+                Matcher::Regex(r#""filename":"preview1.png""#.to_string()),
+                Matcher::Regex(r#"name="files\[1\]"; filename="preview1.png""#.to_string()),
+                Matcher::Regex("Content-Type: image/png".to_string()),
+            ]))
+            .with_status(200)
+            .create();
+
+        let notifier =
+            DiscordNotifier::new(server.url().into()).with_previews(PreviewRenderer::new(None));
+
+        assert!(notifier.notify(&domains).await.is_ok());
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn discord_notify_tags_network() {
+        let mut server = mockito::Server::new();
+
+        let mock = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "embeds": [{ "footer": { "text": "signet" } }]
+            })))
+            .with_status(204)
+            .create();
+
+        let notifier = DiscordNotifier::new(server.url().into()).with_network(Network::Signet);
+
+        assert!(notifier.notify(&test_domains(1)).await.is_ok());
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn discord_send_digest() {
+        let mut server = mockito::Server::new();
+        let domains = test_domains(2);
+        let digest = Digest::new(DigestPeriod::Daily, domains[0].registered_at, &domains, 42);
+
+        let mock = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "embeds": [{
+                    "title": "Daily digest",
+                    "description": "2 domains have been registered in the last 24 hours.",
+                    "fields": [
+                        { "name": "SingleCharacter", "value": "0", "inline": true },
+                        { "name": "VeryShort", "value": "0", "inline": true },
+                        { "name": "Short", "value": "0", "inline": true },
+                        { "name": "Normal", "value": "2", "inline": true },
+                        { "name": "Rarest names", "value": "`domain0.o`\n`domain1.o`", "inline": false },
+                        { "name": "Total registered", "value": "42", "inline": false }
+                    ]
+                }]
+            })))
+            .with_status(204)
+            .create();
+
+        let notifier = DiscordNotifier::new(server.url().into());
+
+        assert!(notifier.send_digest(&digest).await.is_ok());
+        mock.assert();
+    }
+
+    #[test]
+    fn discord_digest_embed_single_registration() {
+        let domains = test_domains(1);
+        let digest = Digest::new(DigestPeriod::Weekly, domains[0].registered_at, &domains, 1);
+
+        assert_eq!(
+            digest_embed(&digest)["description"],
+            "1 domain has been registered in the last 7 days."
+        );
+    }
+
+    #[tokio::test]
+    async fn discord_notify_error_status() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/").with_status(429).create();
+
+        let notifier = DiscordNotifier::new(server.url().into());
+
+        assert!(notifier.notify(&test_domains(1)).await.is_err());
+        mock.assert();
+    }
+
+    #[test]
+    fn discord_webhook_urls() {
+        let valid = [
+            "https://discord.com/api/webhooks/1234/token",
+            "https://discordapp.com/api/webhooks/1234/token",
+            "https://canary.discord.com/api/webhooks/1234/token",
+        ];
+        let invalid = [
+            "http://discord.com/api/webhooks/1234/token",
+            "https://discord.com/channels/1234",
+            "https://discord.com.example.com/api/webhooks/1234/token",
+        ];
+
+        for url in valid {
+            assert!(is_discord_webhook(&Url::parse(url).unwrap()), "{}", url);
+        }
+
+        for url in invalid {
+            assert!(!is_discord_webhook(&Url::parse(url).unwrap()), "{}", url);
+        }
+    }
+}
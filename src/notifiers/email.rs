@@ -0,0 +1,520 @@
+use std::time::Duration;
+
+use bitcoin::Network;
+use handlebars::Handlebars;
+use lettre::{
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    models::{audit_entry::Receipt, registered_domain::RegisteredDomain},
+    notifiers::{
+        alerts::AlertEvent,
+        explorers::{Explorers, Link},
+        mainnet, network_tag, Notifier, NotifyError,
+    },
+    utils::secret::Secret,
+};
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+static DEFAULT_REGISTRATIONS_SUBJECT: &str =
+    "{{#if network}}[{{network}}] {{/if}}{{count}} new domain{{#if plural}}s{{/if}} registered";
+static DEFAULT_REGISTRATIONS_TEXT: &str = "{{#each domains}}{{domain}} has just been registered!
+{{inscription_url}}
+
+{{/each}}";
+static DEFAULT_REGISTRATIONS_HTML: &str = "{{#each domains}}<p><code>{{domain}}</code> has just \
+     been registered!<br><a href=\"{{inscription_url}}\">{{inscription_id}}</a></p>{{/each}}";
+
+static DEFAULT_FAILURES_SUBJECT: &str = "[Xiler{{#if network}} {{network}}{{/if}}] {{title}}";
+static DEFAULT_FAILURES_TEXT: &str = "{{text}}";
+static DEFAULT_FAILURES_HTML: &str = "<p><strong>{{title}}</strong></p><pre>{{text}}</pre>";
+
+/// How the connection to the SMTP server is secured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailTls {
+    /// Upgrades the connection with STARTTLS, port 587 by default.
+    #[default]
+    Starttls,
+    /// Connects over TLS, port 465 by default.
+    Implicit,
+    /// Sends without encryption, port 25 by default. Only meant for a local SMTP sink.
+    None,
+}
+
+/// The recipients of every type of event.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailRecipients {
+    /// Receive the announcements of the registrations routed to the notifier.
+    pub registrations: Vec<String>,
+    /// Receive the ops alerts.
+    pub failures: Vec<String>,
+}
+
+/// The Handlebars templates of an email, the built-in templates are used for parts
+/// that are not set.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailTemplate {
+    pub subject: Option<String>,
+    pub text: Option<String>,
+    pub html: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailTemplates {
+    pub registrations: EmailTemplate,
+    pub failures: EmailTemplate,
+}
+
+/// Sends the registrations and ops alerts by email over SMTP.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailNotifier {
+    host: String,
+    port: Option<u16>,
+    #[serde(default)]
+    tls: EmailTls,
+    username: Option<String>,
+    password: Option<Secret<String>>,
+    /// The sender, e.g. `Xiler Domains <noreply@xiler.net>`.
+    from: String,
+    recipients: EmailRecipients,
+    #[serde(default)]
+    templates: EmailTemplates,
+    #[serde(skip, default = "mainnet")]
+    network: Network,
+    #[serde(skip)]
+    explorers: Explorers,
+}
+
+#[derive(Serialize)]
+struct DomainContext<'a> {
+    domain: &'a str,
+    name: &'a str,
+    inscription_id: &'a str,
+    inscription_url: String,
+    links: Vec<Link>,
+    length: String,
+    registered_at: String,
+}
+
+impl EmailNotifier {
+    pub fn new(
+        host: String,
+        port: Option<u16>,
+        tls: EmailTls,
+        from: String,
+        recipients: EmailRecipients,
+    ) -> Self {
+        Self {
+            host,
+            port,
+            tls,
+            username: None,
+            password: None,
+            from,
+            recipients,
+            templates: EmailTemplates::default(),
+            network: Network::Bitcoin,
+            explorers: Explorers::default(),
+        }
+    }
+
+    /// Tags the announcements with the network, unless it is mainnet.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Links the announcements to the given explorers.
+    pub fn with_explorers(mut self, explorers: Explorers) -> Self {
+        self.explorers = explorers;
+        self
+    }
+
+    /// Whether the notifier has recipients for the ops alerts.
+    pub fn receives_failures(&self) -> bool {
+        !self.recipients.failures.is_empty()
+    }
+
+    /// Sends the alert to the failure recipients.
+    pub async fn send_alert(&self, event: &AlertEvent) -> Result<(), NotifyError> {
+        let context = json!({
+            "network": network_tag(self.network),
+            "title": event.title(),
+            "text": event.text(),
+        });
+
+        self.send(
+            &self.recipients.failures,
+            &self.templates.failures,
+            [
+                DEFAULT_FAILURES_SUBJECT,
+                DEFAULT_FAILURES_TEXT,
+                DEFAULT_FAILURES_HTML,
+            ],
+            &context,
+        )
+        .await
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, NotifyError> {
+        let builder = match self.tls {
+            EmailTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)
+                .map_err(|e| NotifyError::Email(e.to_string()))?,
+            EmailTls::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)
+                .map_err(|e| NotifyError::Email(e.to_string()))?,
+            EmailTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host),
+        };
+
+        let builder = match self.port {
+            Some(port) => builder.port(port),
+            None => builder,
+        };
+
+        let builder = match (&self.username, &self.password) {
+            (Some(username), Some(password)) => builder.credentials(Credentials::new(
+                username.clone(),
+                password.expose().clone(),
+            )),
+            _ => builder,
+        };
+
+        Ok(builder.timeout(Some(SMTP_TIMEOUT)).build())
+    }
+
+    async fn send(
+        &self,
+        recipients: &[String],
+        template: &EmailTemplate,
+        defaults: [&str; 3],
+        context: &serde_json::Value,
+    ) -> Result<(), NotifyError> {
+        let [subject, text, html] = render(template, defaults, context)?;
+
+        let mut message = Message::builder()
+            .from(parse_mailbox(&self.from)?)
+            .subject(subject);
+
+        for recipient in recipients {
+            message = message.to(parse_mailbox(recipient)?);
+        }
+
+        let message = message
+            .multipart(MultiPart::alternative_plain_html(text, html))
+            .map_err(|e| NotifyError::Email(e.to_string()))?;
+
+        self.transport()?
+            .send(message)
+            .await
+            .map_err(|e| NotifyError::Email(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &'static str {
+        "EMAIL"
+    }
+
+    fn batch_size(&self) -> usize {
+        50
+    }
+
+    fn announces_registrations(&self) -> bool {
+        !self.recipients.registrations.is_empty()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.host.is_empty() {
+            return Err("Email host must not be empty".to_string());
+        }
+
+        if self.username.is_some() != self.password.is_some() {
+            return Err("Email username and password must be set together".to_string());
+        }
+
+        for address in [&self.from]
+            .into_iter()
+            .chain(&self.recipients.registrations)
+            .chain(&self.recipients.failures)
+        {
+            parse_mailbox(address).map_err(|e| e.to_string())?;
+        }
+
+        if self.recipients.registrations.is_empty() && self.recipients.failures.is_empty() {
+            return Err("Email notifier must have recipients".to_string());
+        }
+
+        let templates = [&self.templates.registrations, &self.templates.failures];
+        for source in templates
+            .iter()
+            .flat_map(|t| [&t.subject, &t.text, &t.html])
+            .flatten()
+        {
+            handlebars::Template::compile(source)
+                .map_err(|e| format!("Invalid email template: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Emails have no HTTP status or response, so the receipt is empty.
+    async fn notify(&self, domains: &[RegisteredDomain]) -> Result<Receipt, NotifyError> {
+        // Not routed, see `announces_registrations`, so nothing is ever marked as
+        // delivered without being sent.
+        if self.recipients.registrations.is_empty() {
+            return Err(NotifyError::Email(
+                "The email notifier has no registration recipients".to_string(),
+            ));
+        }
+
+        let context = json!({
+            "network": network_tag(self.network),
+            "count": domains.len(),
+            "plural": domains.len() != 1,
+            "domains": domains
+                .iter()
+                .map(|domain| DomainContext {
+                    domain: &domain.domain,
+                    name: domain.domain.trim_end_matches(".o"),
+                    inscription_id: &domain.inscription_id,
+                    inscription_url: self.explorers.url(domain),
+                    links: self.explorers.links(domain),
+                    length: domain.length().to_string(),
+                    registered_at: domain.registered_at.to_rfc3339(),
+                })
+                .collect::<Vec<_>>(),
+        });
+
+        self.send(
+            &self.recipients.registrations,
+            &self.templates.registrations,
+            [
+                DEFAULT_REGISTRATIONS_SUBJECT,
+                DEFAULT_REGISTRATIONS_TEXT,
+                DEFAULT_REGISTRATIONS_HTML,
+            ],
+            &context,
+        )
+        .await?;
+
+        Ok(Receipt::default())
+    }
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, NotifyError> {
+    address
+        .parse()
+        .map_err(|e| NotifyError::Email(format!("Invalid email address {:?}: {}", address, e)))
+}
+
+/// Renders the subject, plain text and HTML of an email. Only the HTML is escaped.
+fn render(
+    template: &EmailTemplate,
+    defaults: [&str; 3],
+    context: &serde_json::Value,
+) -> Result<[String; 3], NotifyError> {
+    let mut text = Handlebars::new();
+    text.register_escape_fn(handlebars::no_escape);
+    let html = Handlebars::new();
+
+    let [subject, plain, markup] = defaults;
+    let render = |registry: &Handlebars, source: &Option<String>, default: &str| {
+        registry
+            .render_template(source.as_deref().unwrap_or(default), context)
+            .map_err(|e| NotifyError::Email(format!("Failed to render email: {}", e)))
+    };
+
+    Ok([
+        render(&text, &template.subject, subject)?,
+        render(&text, &template.text, plain)?,
+        render(&html, &template.html, markup)?,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::notifiers::{alerts::AlertCondition, test_domains};
+
+    /// Starts a local SMTP sink that accepts a single connection, and returns its
+    /// port and the received messages.
+    async fn smtp_sink() -> (u16, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let messages = Arc::new(Mutex::new(vec![]));
+        let received = messages.clone();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+
+            writer.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+
+            let mut data: Option<String> = None;
+            while let Some(line) = lines.next_line().await.unwrap() {
+                if let Some(message) = data.as_mut() {
+                    if line == "." {
+                        received.lock().unwrap().push(data.take().unwrap());
+                        writer.write_all(b"250 queued\r\n").await.unwrap();
+                    } else {
+                        message.push_str(&line);
+                        message.push('\n');
+                    }
+                    continue;
+                }
+
+                let reply: &[u8] = match line.get(..4).unwrap_or_default() {
+                    "EHLO" => b"250-sink\r\n250 8BITMIME\r\n",
+                    "DATA" => {
+                        data = Some(String::new());
+                        b"354 go ahead\r\n"
+                    }
+                    "QUIT" => {
+                        writer.write_all(b"221 bye\r\n").await.unwrap();
+                        break;
+                    }
+                    _ => b"250 ok\r\n",
+                };
+                writer.write_all(reply).await.unwrap();
+            }
+        });
+
+        (port, messages)
+    }
+
+    fn notifier(port: u16) -> EmailNotifier {
+        EmailNotifier::new(
+            "127.0.0.1".to_string(),
+            Some(port),
+            EmailTls::None,
+            "Xiler Domains <noreply@xiler.net>".to_string(),
+            EmailRecipients {
+                registrations: vec!["partner@example.com".to_string()],
+                failures: vec!["ops@example.com".to_string()],
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn email_notify() {
+        let (port, messages) = smtp_sink().await;
+        let domains = test_domains(2);
+
+        assert!(notifier(port).notify(&domains).await.is_ok());
+
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("To: partner@example.com"));
+        assert!(messages[0].contains("Subject: 2 new domains registered"));
+        assert!(messages[0].contains("domain1.o has just been registered!"));
+        assert!(messages[0].contains("Content-Type: text/html"));
+    }
+
+    #[tokio::test]
+    async fn email_send_alert() {
+        let (port, messages) = smtp_sink().await;
+        let event = AlertEvent::Recovery {
+            condition: AlertCondition::DatabaseUnreachable,
+            since: chrono::Utc::now(),
+        };
+
+        assert!(notifier(port).send_alert(&event).await.is_ok());
+
+        let messages = messages.lock().unwrap();
+        assert!(messages[0].contains("To: ops@example.com"));
+        assert!(messages[0].contains("Subject: [Xiler] Recovered: Database unreachable"));
+    }
+
+    #[tokio::test]
+    async fn email_notify_unreachable_server() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        assert!(matches!(
+            notifier(port).notify(&test_domains(1)).await,
+            Err(NotifyError::Email(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn email_notify_without_registration_recipients() {
+        let mut notifier = notifier(25);
+        notifier.recipients.registrations.clear();
+
+        assert!(!notifier.announces_registrations());
+        assert!(matches!(
+            notifier.notify(&test_domains(1)).await,
+            Err(NotifyError::Email(_))
+        ));
+    }
+
+    #[test]
+    fn email_render_escapes_only_html() {
+        let template = EmailTemplate {
+            subject: Some("{{value}}".to_string()),
+            text: None,
+            html: None,
+        };
+        let [subject, _, html] = render(
+            &template,
+            ["", "", "<p>{{value}}</p>"],
+            &json!({ "value": "<b>" }),
+        )
+        .unwrap();
+
+        assert_eq!(subject, "<b>");
+        assert_eq!(html, "<p>&lt;b&gt;</p>");
+    }
+
+    #[test]
+    fn email_validate() {
+        let mut notifier = notifier(25);
+        assert!(notifier.validate().is_ok());
+
+        notifier.recipients.failures = vec!["not an address".to_string()];
+        assert!(notifier.validate().is_err());
+
+        notifier.recipients = EmailRecipients::default();
+        assert!(notifier.validate().is_err());
+    }
+
+    #[test]
+    fn email_deserialize() {
+        let notifier: EmailNotifier = toml::from_str(
+            r#"
+                host = "smtp.example.com"
+                tls = "implicit"
+                username = "xiler"
+                password = "secret"
+                from = "noreply@xiler.net"
+                recipients = { registrations = ["partner@example.com"] }
+                templates.registrations.subject = "New: {{#each domains}}{{domain}} {{/each}}"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(notifier.tls, EmailTls::Implicit);
+        assert!(!notifier.receives_failures());
+        assert!(notifier.validate().is_ok());
+    }
+}
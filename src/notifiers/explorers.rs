@@ -0,0 +1,213 @@
+use bitcoin::Network;
+use color_eyre::eyre::{bail, Result};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::models::registered_domain::RegisteredDomain;
+
+static PLACEHOLDERS: &[&str] = &["{inscription_id}", "{reveal_tx}", "{domain}", "{name}"];
+
+/// An explorer linked in announcements.
+///
+/// The URL is a template with the `{inscription_id}`, `{reveal_tx}`, `{domain}` and
+/// `{name}` (the domain without `.o`) placeholders.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Explorer {
+    pub name: String,
+    pub url: String,
+}
+
+impl Explorer {
+    fn new(name: &str, url: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    fn render(&self, domain: &RegisteredDomain) -> String {
+        self.url
+            .replace("{inscription_id}", &domain.inscription_id)
+            .replace("{reveal_tx}", &domain.reveal_tx)
+            .replace("{domain}", &domain.domain)
+            .replace("{name}", domain.domain.trim_end_matches(".o"))
+    }
+}
+
+/// A link to a domain on an explorer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Link {
+    pub name: String,
+    pub url: String,
+}
+
+/// The explorers linked in announcements, the first one is the main link of a domain.
+#[derive(Debug, Clone)]
+pub struct Explorers {
+    explorers: Vec<Explorer>,
+}
+
+impl Default for Explorers {
+    fn default() -> Self {
+        Self::defaults(Network::Bitcoin)
+    }
+}
+
+impl Explorers {
+    /// Checks the explorers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no explorers, a name is used twice or a URL
+    /// template is invalid.
+    pub fn new(explorers: Vec<Explorer>) -> Result<Self> {
+        if explorers.is_empty() {
+            bail!("At least one explorer is required");
+        }
+
+        for (index, explorer) in explorers.iter().enumerate() {
+            if explorers[..index].iter().any(|e| e.name == explorer.name) {
+                bail!("Explorer `{}` is defined more than once", explorer.name);
+            }
+
+            let example = PLACEHOLDERS
+                .iter()
+                .fold(explorer.url.clone(), |url, placeholder| {
+                    url.replace(placeholder, "x")
+                });
+
+            if example.contains('{') || example.contains('}') {
+                bail!(
+                    "Explorer `{}` uses an unknown placeholder, expected one of {}",
+                    explorer.name,
+                    PLACEHOLDERS.join(", ")
+                );
+            }
+
+            match Url::parse(&example) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => bail!("Explorer `{}` url is not a http(s) URL", explorer.name),
+            }
+        }
+
+        Ok(Self { explorers })
+    }
+
+    /// Returns the default explorers of a network.
+    pub fn defaults(network: Network) -> Self {
+        let explorers = match network {
+            Network::Testnet => vec![
+                Explorer::new(
+                    "Ordinals",
+                    "https://testnet.ordinals.com/inscription/{inscription_id}",
+                ),
+                Explorer::new("Mempool", "https://mempool.space/testnet/tx/{reveal_tx}"),
+            ],
+            Network::Signet => vec![
+                Explorer::new(
+                    "Ordinals",
+                    "https://signet.ordinals.com/inscription/{inscription_id}",
+                ),
+                Explorer::new("Mempool", "https://mempool.space/signet/tx/{reveal_tx}"),
+            ],
+            // The default address of `ord server`.
+            Network::Regtest => vec![Explorer::new(
+                "Ord",
+                "http://localhost/inscription/{inscription_id}",
+            )],
+            _ => vec![
+                Explorer::new(
+                    "OrdinalsWallet",
+                    "https://ordinalswallet.com/inscription/{inscription_id}",
+                ),
+                Explorer::new(
+                    "Ordinals",
+                    "https://ordinals.com/inscription/{inscription_id}",
+                ),
+                Explorer::new("Mempool", "https://mempool.space/tx/{reveal_tx}"),
+            ],
+        };
+
+        Self { explorers }
+    }
+
+    /// Returns the link of a domain on the first explorer.
+    pub fn url(&self, domain: &RegisteredDomain) -> String {
+        self.explorers[0].render(domain)
+    }
+
+    /// Returns the links of a domain on every explorer.
+    pub fn links(&self, domain: &RegisteredDomain) -> Vec<Link> {
+        self.explorers
+            .iter()
+            .map(|explorer| Link {
+                name: explorer.name.clone(),
+                url: explorer.render(domain),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifiers::test_domains;
+
+    #[test]
+    fn explorers_render_placeholders() {
+        let domain = test_domains(1).remove(0);
+        let explorers = Explorers::new(vec![
+            Explorer::new("Xiler", "https://xiler.net/domains/{name}?full={domain}"),
+            Explorer::new("Mempool", "https://mempool.space/tx/{reveal_tx}"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            explorers.url(&domain),
+            "https://xiler.net/domains/domain0?full=domain0.o"
+        );
+        assert_eq!(
+            explorers.links(&domain)[1],
+            Link {
+                name: "Mempool".to_string(),
+                url: format!("https://mempool.space/tx/{}", domain.reveal_tx),
+            }
+        );
+    }
+
+    #[test]
+    fn explorers_defaults_per_network() {
+        let domain = test_domains(1).remove(0);
+
+        assert_eq!(
+            Explorers::default().url(&domain),
+            format!(
+                "https://ordinalswallet.com/inscription/{}",
+                domain.inscription_id
+            )
+        );
+        assert!(Explorers::defaults(Network::Signet)
+            .links(&domain)
+            .iter()
+            .all(|link| link.url.contains("signet")));
+        assert!(Explorers::defaults(Network::Testnet)
+            .links(&domain)
+            .iter()
+            .all(|link| link.url.contains("testnet")));
+    }
+
+    #[test]
+    fn explorers_invalid() {
+        assert!(Explorers::new(vec![]).is_err());
+        assert!(
+            Explorers::new(vec![Explorer::new("Typo", "https://x.com/{inscription}")]).is_err()
+        );
+        assert!(Explorers::new(vec![Explorer::new("Relative", "/tx/{reveal_tx}")]).is_err());
+        assert!(Explorers::new(vec![
+            Explorer::new("Twice", "https://a.com/{reveal_tx}"),
+            Explorer::new("Twice", "https://b.com/{reveal_tx}"),
+        ])
+        .is_err());
+    }
+}
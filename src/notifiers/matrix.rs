@@ -0,0 +1,260 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bitcoin::Network;
+use chrono::Utc;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    models::{audit_entry::Receipt, registered_domain::RegisteredDomain},
+    notifiers::{
+        escape_html, explorers::Explorers, mainnet, network_prefix, send, Notifier, NotifyError,
+    },
+    utils::secret::Secret,
+};
+
+/// Counter to keep transaction ids unique within the same millisecond.
+static TRANSACTION_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Announces domains to a Matrix room through the client-server API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MatrixNotifier {
+    homeserver_url: String,
+    access_token: Secret<String>,
+    /// The internal room id, e.g. `!abc:matrix.org`.
+    room_id: String,
+    #[serde(skip, default = "mainnet")]
+    network: Network,
+    #[serde(skip)]
+    explorers: Explorers,
+    #[serde(skip)]
+    client: reqwest::Client,
+}
+
+impl MatrixNotifier {
+    pub fn new(homeserver_url: String, access_token: Secret<String>, room_id: String) -> Self {
+        Self {
+            homeserver_url,
+            access_token,
+            room_id,
+            network: Network::Bitcoin,
+            explorers: Explorers::default(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Tags the announcements with the network, unless it is mainnet.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Links the announcements to the given explorers.
+    pub fn with_explorers(mut self, explorers: Explorers) -> Self {
+        self.explorers = explorers;
+        self
+    }
+
+    fn send_url(&self) -> Option<Url> {
+        let transaction_id = format!(
+            "xiler-{}-{}",
+            Utc::now().timestamp_millis(),
+            TRANSACTION_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let mut url = Url::parse(&self.homeserver_url).ok()?;
+        url.path_segments_mut().ok()?.pop_if_empty().extend([
+            "_matrix",
+            "client",
+            "v3",
+            "rooms",
+            &self.room_id,
+            "send",
+            "m.room.message",
+            &transaction_id,
+        ]);
+
+        Some(url)
+    }
+}
+
+impl Notifier for MatrixNotifier {
+    fn name(&self) -> &'static str {
+        "MATRIX"
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match Url::parse(&self.homeserver_url) {
+            Ok(url) if !url.cannot_be_a_base() => {}
+            Ok(_) => return Err("Matrix homeserver_url must be a http(s) URL".to_string()),
+            Err(e) => return Err(format!("Matrix homeserver_url is not a valid URL: {}", e)),
+        }
+
+        if self.access_token.expose().is_empty() {
+            return Err("Matrix access_token must not be empty".to_string());
+        }
+
+        if !self.room_id.starts_with('!') || !self.room_id.contains(':') {
+            return Err("Matrix room_id must be a room id like !abc:matrix.org".to_string());
+        }
+
+        Ok(())
+    }
+
+    async fn notify(&self, domains: &[RegisteredDomain]) -> Result<Receipt, NotifyError> {
+        let prefix = network_prefix(self.network);
+
+        let body = domains
+            .iter()
+            .map(|domain| {
+                format!(
+                    "{}{} has just been registered! {}",
+                    prefix,
+                    domain.domain,
+                    self.explorers.url(domain)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let formatted_body = domains
+            .iter()
+            .map(|domain| {
+                format!(
+                    "{}<code>{}</code> has just been registered!<br><a href=\"{}\">{}</a>",
+                    prefix,
+                    escape_html(&domain.domain),
+                    escape_html(&self.explorers.url(domain)),
+                    escape_html(&domain.inscription_id)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("<br><br>");
+
+        let json = json!({
+            "msgtype": "m.notice",
+            "body": body,
+            "format": "org.matrix.custom.html",
+            "formatted_body": formatted_body
+        });
+
+        let url = self
+            .send_url()
+            .expect("homeserver_url is validated on startup");
+
+        send(
+            self.client
+                .put(url)
+                .bearer_auth(self.access_token.expose())
+                .json(&json),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifiers::test_domains;
+    use mockito::Matcher;
+
+    #[tokio::test]
+    async fn matrix_notify() {
+        let mut server = mockito::Server::new();
+        let domains = test_domains(1);
+
+        let mock = server
+            .mock(
+                "PUT",
+                Matcher::Regex(
+                    r"^/_matrix/client/v3/rooms/!room:matrix\.org/send/m\.room\.message/xiler-\d+-\d+$"
+                        .to_string(),
+                ),
+            )
+            .match_header("authorization", "Bearer token")
+            .match_body(Matcher::Json(json!({
+                "msgtype": "m.notice",
+                "body": format!(
+                    "domain0.o has just been registered! https://ordinalswallet.com/inscription/{}",
+                    domains[0].inscription_id
+                ),
+                "format": "org.matrix.custom.html",
+                "formatted_body": format!(
+                    "<code>domain0.o</code> has just been registered!<br><a href=\"https://ordinalswallet.com/inscription/{0}\">{0}</a>",
+                    domains[0].inscription_id
+                )
+            })))
+            .with_status(200)
+            .with_body(r#"{"event_id":"$event"}"#)
+            .create();
+
+        let notifier = MatrixNotifier::new(
+            server.url(),
+            "token".to_string().into(),
+            "!room:matrix.org".to_string(),
+        );
+
+        assert!(notifier.notify(&domains).await.is_ok());
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn matrix_notify_escapes_html() {
+        let mut server = mockito::Server::new();
+        let mut domains = test_domains(1);
+        domains[0].domain = "<b>bold</b>.o".to_string();
+
+        let mock = server
+            .mock("PUT", Matcher::Any)
+            .match_body(Matcher::PartialJson(json!({
+                "body": format!(
+                    "<b>bold</b>.o has just been registered! https://ordinalswallet.com/inscription/{}",
+                    domains[0].inscription_id
+                ),
+                "formatted_body": format!(
+                    "<code>&lt;b&gt;bold&lt;/b&gt;.o</code> has just been registered!<br><a href=\"https://ordinalswallet.com/inscription/{0}\">{0}</a>",
+                    domains[0].inscription_id
+                )
+            })))
+            .with_status(200)
+            .create();
+
+        let notifier = MatrixNotifier::new(
+            server.url(),
+            "token".to_string().into(),
+            "!room:matrix.org".to_string(),
+        );
+
+        assert!(notifier.notify(&domains).await.is_ok());
+        mock.assert();
+    }
+
+    #[test]
+    fn matrix_transaction_ids_are_unique() {
+        let notifier = MatrixNotifier::new(
+            "https://matrix.org".to_string(),
+            "token".to_string().into(),
+            "!room:matrix.org".to_string(),
+        );
+
+        assert_ne!(notifier.send_url().unwrap(), notifier.send_url().unwrap());
+    }
+
+    #[test]
+    fn matrix_validate() {
+        let valid = MatrixNotifier::new(
+            "https://matrix.org".to_string(),
+            "token".to_string().into(),
+            "!room:matrix.org".to_string(),
+        );
+        let alias = MatrixNotifier::new(
+            "https://matrix.org".to_string(),
+            "token".to_string().into(),
+            "#xiler:matrix.org".to_string(),
+        );
+
+        assert!(valid.validate().is_ok());
+        assert!(alias.validate().is_err());
+    }
+}
@@ -0,0 +1,339 @@
+pub mod alerts;
+pub mod conditions;
+pub mod digest;
+pub mod discord;
+pub mod email;
+pub mod explorers;
+pub mod matrix;
+pub mod outbox;
+pub mod routes;
+pub mod slack;
+pub mod telegram;
+pub mod templates;
+pub mod webhook;
+
+use bitcoin::Network;
+use serde::Deserialize;
+use tracing::{field, instrument};
+
+use crate::{
+    models::{audit_entry::Receipt, registered_domain::RegisteredDomain},
+    notifiers::explorers::Explorers,
+    utils::{request::execute, telemetry},
+};
+
+pub use discord::DiscordNotifier;
+pub use email::EmailNotifier;
+pub use matrix::MatrixNotifier;
+pub use routes::{Route, Routes};
+pub use slack::SlackNotifier;
+pub use telegram::TelegramNotifier;
+pub use webhook::WebhookNotifier;
+
+/// An error while sending a notification.
+#[derive(Debug)]
+pub enum NotifyError {
+    /// The URL is removed from the error, as it often contains a token.
+    Http(reqwest::Error),
+    /// The response has an error status.
+    Status(Receipt),
+    Email(String),
+}
+
+impl From<reqwest::Error> for NotifyError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Http(e.without_url())
+    }
+}
+
+impl std::fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Http(e) => write!(f, "{}", e),
+            Self::Status(receipt) => write!(
+                f,
+                "HTTP status {}: {}",
+                receipt.status.unwrap_or_default(),
+                receipt.response.as_deref().unwrap_or_default()
+            ),
+            Self::Email(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for NotifyError {}
+
+/// Sends the request of a notifier, a response with an error status fails the
+/// notification.
+async fn send(request: reqwest::RequestBuilder) -> Result<Receipt, NotifyError> {
+    let receipt = execute(request).await?;
+
+    match receipt.is_success() {
+        true => Ok(receipt),
+        false => Err(NotifyError::Status(receipt)),
+    }
+}
+
+/// Announces newly registered domains.
+pub trait Notifier {
+    /// The name of the notifier, used in logs.
+    fn name(&self) -> &'static str;
+
+    /// The maximum amount of domains that fit in a single message.
+    fn batch_size(&self) -> usize {
+        10
+    }
+
+    /// Whether the notifier announces registrations. Notifiers that don't, like an
+    /// email notifier for ops alerts only, are left out of the routes.
+    fn announces_registrations(&self) -> bool {
+        true
+    }
+
+    /// Checks the configuration of the notifier.
+    fn validate(&self) -> Result<(), String>;
+
+    /// Sends a single message announcing the given domains.
+    ///
+    /// The amount of domains never exceeds [`Notifier::batch_size`]. Returns what was
+    /// sent and the response, for the audit log.
+    async fn notify(&self, domains: &[RegisteredDomain]) -> Result<Receipt, NotifyError>;
+}
+
+/// A configured notifier with the id that keys its announcements in the outbox.
+///
+/// Pending announcements follow the id, so it has to stay the same while the
+/// notifier is configured, also when the notifiers are reordered.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyedNotifier {
+    pub id: String,
+    #[serde(flatten)]
+    pub notifier: AnyNotifier,
+}
+
+impl KeyedNotifier {
+    pub fn new(id: &str, notifier: AnyNotifier) -> Self {
+        Self {
+            id: id.to_string(),
+            notifier,
+        }
+    }
+}
+
+/// Any of the supported notifiers, as configured in the `notifiers` list.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AnyNotifier {
+    Discord(DiscordNotifier),
+    Slack(SlackNotifier),
+    Telegram(TelegramNotifier),
+    Matrix(MatrixNotifier),
+    Webhook(WebhookNotifier),
+    Email(Box<EmailNotifier>),
+}
+
+impl AnyNotifier {
+    /// Tags the announcements of the notifier with the network, unless it is mainnet.
+    pub fn with_network(self, network: Network) -> Self {
+        match self {
+            Self::Discord(notifier) => Self::Discord(notifier.with_network(network)),
+            Self::Slack(notifier) => Self::Slack(notifier.with_network(network)),
+            Self::Telegram(notifier) => Self::Telegram(notifier.with_network(network)),
+            Self::Matrix(notifier) => Self::Matrix(notifier.with_network(network)),
+            Self::Webhook(notifier) => Self::Webhook(notifier.with_network(network)),
+            Self::Email(notifier) => Self::Email(Box::new(notifier.with_network(network))),
+        }
+    }
+
+    /// Links the announcements of the notifier to the given explorers.
+    pub fn with_explorers(self, explorers: Explorers) -> Self {
+        match self {
+            Self::Discord(notifier) => Self::Discord(notifier.with_explorers(explorers)),
+            Self::Slack(notifier) => Self::Slack(notifier.with_explorers(explorers)),
+            Self::Telegram(notifier) => Self::Telegram(notifier.with_explorers(explorers)),
+            Self::Matrix(notifier) => Self::Matrix(notifier.with_explorers(explorers)),
+            Self::Webhook(notifier) => Self::Webhook(notifier.with_explorers(explorers)),
+            Self::Email(notifier) => Self::Email(Box::new(notifier.with_explorers(explorers))),
+        }
+    }
+}
+
+impl Notifier for AnyNotifier {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Discord(notifier) => notifier.name(),
+            Self::Slack(notifier) => notifier.name(),
+            Self::Telegram(notifier) => notifier.name(),
+            Self::Matrix(notifier) => notifier.name(),
+            Self::Webhook(notifier) => notifier.name(),
+            Self::Email(notifier) => notifier.name(),
+        }
+    }
+
+    fn batch_size(&self) -> usize {
+        match self {
+            Self::Discord(notifier) => notifier.batch_size(),
+            Self::Slack(notifier) => notifier.batch_size(),
+            Self::Telegram(notifier) => notifier.batch_size(),
+            Self::Matrix(notifier) => notifier.batch_size(),
+            Self::Webhook(notifier) => notifier.batch_size(),
+            Self::Email(notifier) => notifier.batch_size(),
+        }
+    }
+
+    fn announces_registrations(&self) -> bool {
+        match self {
+            Self::Discord(notifier) => notifier.announces_registrations(),
+            Self::Slack(notifier) => notifier.announces_registrations(),
+            Self::Telegram(notifier) => notifier.announces_registrations(),
+            Self::Matrix(notifier) => notifier.announces_registrations(),
+            Self::Webhook(notifier) => notifier.announces_registrations(),
+            Self::Email(notifier) => notifier.announces_registrations(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Discord(notifier) => notifier.validate(),
+            Self::Slack(notifier) => notifier.validate(),
+            Self::Telegram(notifier) => notifier.validate(),
+            Self::Matrix(notifier) => notifier.validate(),
+            Self::Webhook(notifier) => notifier.validate(),
+            Self::Email(notifier) => notifier.validate(),
+        }
+    }
+
+    #[instrument(
+        skip_all,
+        fields(notifier = self.name(), domains = domains.len(), otel.status_message = field::Empty)
+    )]
+    async fn notify(&self, domains: &[RegisteredDomain]) -> Result<Receipt, NotifyError> {
+        match self {
+            Self::Discord(notifier) => notifier.notify(domains).await,
+            Self::Slack(notifier) => notifier.notify(domains).await,
+            Self::Telegram(notifier) => notifier.notify(domains).await,
+            Self::Matrix(notifier) => notifier.notify(domains).await,
+            Self::Webhook(notifier) => notifier.notify(domains).await,
+            Self::Email(notifier) => notifier.notify(domains).await,
+        }
+        .inspect_err(telemetry::record_error)
+    }
+}
+
+/// The network of notifiers that are not configured otherwise.
+fn mainnet() -> Network {
+    Network::Bitcoin
+}
+
+/// Returns the name of a network as used in the configuration, e.g. `mainnet`.
+pub fn network_name(network: Network) -> String {
+    match network {
+        Network::Bitcoin => "mainnet".to_string(),
+        network => network.to_string(),
+    }
+}
+
+/// Returns the tag of announcements on other networks than mainnet, e.g. `signet`.
+pub fn network_tag(network: Network) -> Option<String> {
+    (network != Network::Bitcoin).then(|| network_name(network))
+}
+
+/// Returns the prefix of plain text announcements on other networks than mainnet,
+/// e.g. `[signet] `.
+pub fn network_prefix(network: Network) -> String {
+    network_tag(network)
+        .map(|tag| format!("[{}] ", tag))
+        .unwrap_or_default()
+}
+
+/// Escapes user provided text for HTML messages, also within attribute values.
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+pub fn test_domains(amount: usize) -> Vec<RegisteredDomain> {
+    use chrono::TimeZone;
+
+    (0..amount)
+        .map(|i| {
+            RegisteredDomain::new(
+                format!("domain{}.o", i),
+                "bd3bfa98c592fdb6ee81d4655082c43f27b63b05c706bd47bac4e1b715eab7a6".to_string(),
+                chrono::Utc.timestamp_opt(1698000000, 0).unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyed_notifier_deserialize() {
+        let toml = r#"
+            [[notifiers]]
+            id = "discord"
+            type = "discord"
+            webhook_url = "https://discord.com/api/webhooks/1234/token"
+
+            [[notifiers]]
+            id = "slack"
+            type = "slack"
+            webhook_url = "https://hooks.slack.com/services/T000/B000/XXXX"
+
+            [[notifiers]]
+            id = "telegram"
+            type = "telegram"
+            bot_token = "1234:token"
+            chat_id = "@xiler"
+
+            [[notifiers]]
+            id = "matrix"
+            type = "matrix"
+            homeserver_url = "https://matrix.org"
+            access_token = "token"
+            room_id = "!room:matrix.org"
+
+            [[notifiers]]
+            id = "webhook"
+            type = "webhook"
+            url = "https://xiler.net/hooks/registrations"
+            secret = "0123456789abcdef"
+
+            [[notifiers]]
+            id = "email"
+            type = "email"
+            host = "smtp.example.com"
+            from = "noreply@xiler.net"
+            recipients = { registrations = ["partner@example.com"] }
+        "#;
+
+        #[derive(Deserialize)]
+        struct Notifiers {
+            notifiers: Vec<KeyedNotifier>,
+        }
+
+        let notifiers = toml::from_str::<Notifiers>(toml).unwrap().notifiers;
+        let names = notifiers
+            .iter()
+            .map(|n| n.notifier.name())
+            .collect::<Vec<_>>();
+        let ids = notifiers.iter().map(|n| n.id.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            vec!["DISCORD", "SLACK", "TELEGRAM", "MATRIX", "WEBHOOK", "EMAIL"]
+        );
+        assert_eq!(
+            ids,
+            vec!["discord", "slack", "telegram", "matrix", "webhook", "email"]
+        );
+        assert!(notifiers.iter().all(|n| n.notifier.validate().is_ok()));
+    }
+}
@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use futures::future::join_all;
+use tracing::{debug, error, info, info_span, Instrument};
+
+use crate::{
+    db::{AuditRepository, OutboxRepository},
+    models::{
+        audit_entry::{AuditEntry, AuditKind},
+        outbox_message::OutboxMessage,
+    },
+    notifiers::{AnyNotifier, Notifier, NotifyError, Routes},
+    server::metrics::{metrics, ErrorKind},
+    utils::shutdown::Shutdown,
+};
+
+/// The maximum amount of messages fetched from the outbox at once.
+const FETCH_LIMIT: i64 = 100;
+/// The maximum amount of seconds between two delivery attempts.
+const MAX_BACKOFF: i64 = 60 * 60;
+/// The error of messages whose notifier is no longer configured.
+static UNKNOWN_NOTIFIER_ERROR: &str = "The notifier is no longer configured";
+
+/// Drains the notification outbox.
+///
+/// Checks the outbox every `retry_interval` seconds, messages that fail to deliver
+/// are retried with an exponential backoff until `max_attempts` is reached.
+///
+/// When the shutdown is requested, the messages that are due are delivered once
+/// more before the worker stops. The others stay in the outbox for the next start.
+pub async fn run_outbox_worker<R: OutboxRepository + AuditRepository>(
+    repository: R,
+    routes: Routes,
+    retry_interval: u64,
+    max_attempts: u32,
+    mut shutdown: Shutdown,
+) {
+    info!("[OUTBOX] Started notification worker");
+
+    loop {
+        match deliver_pending(&repository, &routes, retry_interval, max_attempts).await {
+            // More messages might be due right away.
+            Ok(delivered) if delivered > 0 && !shutdown.is_requested() => continue,
+            Ok(_) => {}
+            Err(e) => {
+                error!("[OUTBOX] Error reading the outbox: {:?}", e);
+                metrics().error(ErrorKind::Outbox);
+            }
+        }
+
+        if shutdown.is_requested() {
+            break;
+        }
+
+        shutdown.sleep(retry_interval).await;
+    }
+
+    info!("[OUTBOX] Stopped notification worker");
+}
+
+/// Delivers the messages in the outbox that are due, and returns the amount of
+/// delivered messages.
+///
+/// The messages of every notifier are sent in batches of [`Notifier::batch_size`],
+/// all notifiers run concurrently. Messages of notifiers that are no longer
+/// configured are marked as failed, instead of staying pending forever.
+pub async fn deliver_pending<R: OutboxRepository + AuditRepository>(
+    repository: &R,
+    routes: &Routes,
+    retry_interval: u64,
+    max_attempts: u32,
+) -> sqlx::Result<usize> {
+    let notifiers: HashMap<String, &AnyNotifier> = routes.keyed_notifiers().into_iter().collect();
+    let keys = notifiers.keys().cloned().collect::<Vec<_>>();
+
+    let mut unknown = repository
+        .fail_unknown_notifications(&keys, UNKNOWN_NOTIFIER_ERROR)
+        .await?;
+    if !unknown.is_empty() {
+        error!(
+            "[OUTBOX] Gave up on {} messages of notifiers that are no longer configured: {}",
+            unknown.len(),
+            {
+                unknown.sort();
+                unknown.dedup();
+                unknown.join(", ")
+            }
+        );
+        metrics().error(ErrorKind::UnknownNotifier);
+    }
+
+    let messages = repository
+        .get_pending_notifications(&keys, FETCH_LIMIT)
+        .await?;
+
+    if messages.is_empty() {
+        return Ok(0);
+    }
+
+    debug!("[OUTBOX] Delivering {} messages", messages.len());
+
+    let mut by_notifier: Vec<(&AnyNotifier, Vec<OutboxMessage>)> = vec![];
+    for message in messages {
+        let notifier = notifiers[&message.notifier];
+
+        match by_notifier
+            .iter_mut()
+            .find(|(n, _)| std::ptr::eq(*n, notifier))
+        {
+            Some((_, messages)) => messages.push(message),
+            None => by_notifier.push((notifier, vec![message])),
+        }
+    }
+
+    let delivered = join_all(by_notifier.iter().map(|(notifier, messages)| {
+        deliver(repository, notifier, messages, retry_interval, max_attempts)
+    }))
+    .await;
+
+    delivered
+        .into_iter()
+        .try_fold(0, |total, delivered| Ok(total + delivered?))
+}
+
+/// Delivers the messages of a single notifier, stops at the first failed batch.
+async fn deliver<R: OutboxRepository + AuditRepository>(
+    repository: &R,
+    notifier: &AnyNotifier,
+    messages: &[OutboxMessage],
+    retry_interval: u64,
+    max_attempts: u32,
+) -> sqlx::Result<usize> {
+    let mut delivered = 0;
+
+    for batch in messages.chunks(notifier.batch_size()) {
+        // Ties the announcement to the sync cycle that published the domains.
+        let inscription_ids = batch
+            .iter()
+            .map(|m| m.domain.inscription_id.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        let span = info_span!("deliver", notifier = notifier.name(), inscription_ids);
+
+        if !deliver_batch(repository, notifier, batch, retry_interval, max_attempts)
+            .instrument(span)
+            .await?
+        {
+            break;
+        }
+
+        delivered += batch.len();
+    }
+
+    if delivered > 0 {
+        info!("[{}] Announced {} domains", notifier.name(), delivered);
+    }
+
+    Ok(delivered)
+}
+
+/// Delivers a batch of messages, and returns whether it was delivered.
+async fn deliver_batch<R: OutboxRepository + AuditRepository>(
+    repository: &R,
+    notifier: &AnyNotifier,
+    batch: &[OutboxMessage],
+    retry_interval: u64,
+    max_attempts: u32,
+) -> sqlx::Result<bool> {
+    let ids = batch.iter().map(|m| m.id).collect::<Vec<_>>();
+    let domains = batch.iter().map(|m| m.domain.clone()).collect::<Vec<_>>();
+
+    let timer = metrics()
+        .notify_duration
+        .with_label_values(&[notifier.name()])
+        .start_timer();
+    let started_at = Utc::now();
+    let result = notifier.notify(&domains).await;
+    timer.observe_duration();
+
+    // A response with an error status is recorded with its receipt.
+    let receipt = match &result {
+        Ok(receipt) | Err(NotifyError::Status(receipt)) => Ok(receipt),
+        Err(e) => Err(e),
+    };
+    let entry = AuditEntry::new(
+        AuditKind::Announce,
+        batch[0].notifier.clone(),
+        &domains,
+        receipt,
+        started_at,
+    );
+    if let Err(e) = repository.record_attempt(&entry).await {
+        error!(
+            "[{}] Error recording the announcement in the audit log: {:?}",
+            notifier.name(),
+            e
+        );
+    }
+
+    let e = match result {
+        Ok(_) => {
+            repository.mark_delivered(&ids).await?;
+            metrics()
+                .domains_announced
+                .with_label_values(&[notifier.name()])
+                .inc_by(batch.len() as u64);
+            return Ok(true);
+        }
+        Err(e) => e,
+    };
+
+    metrics().error(ErrorKind::Notify);
+    let attempts = batch.iter().map(|m| m.attempts).max().unwrap_or(0) + 1;
+
+    let retry_at = if attempts as u32 >= max_attempts {
+        error!(
+            "[{}] Giving up on {} messages after {} attempts: {:?}",
+            notifier.name(),
+            batch.len(),
+            attempts,
+            e
+        );
+        None
+    } else {
+        let backoff = backoff(retry_interval, attempts);
+        error!(
+            "[{}] Error sending notification, retrying in {} seconds: {:?}",
+            notifier.name(),
+            backoff,
+            e
+        );
+        Some((Utc::now() + Duration::seconds(backoff)).naive_utc())
+    };
+
+    repository
+        .mark_failed(&ids, &e.to_string(), retry_at)
+        .await?;
+
+    Ok(false)
+}
+
+/// Returns the amount of seconds to wait after the given amount of failed attempts.
+fn backoff(retry_interval: u64, attempts: i32) -> i64 {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+
+    (retry_interval as i64)
+        .saturating_mul(2_i64.pow(exponent))
+        .min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::repository::memory::MemoryRepository,
+        models::checkpoint::Checkpoint,
+        notifiers::{test_domains, KeyedNotifier, SlackNotifier},
+    };
+
+    fn routes(url: String) -> Routes {
+        Routes::new(
+            vec![],
+            vec![KeyedNotifier::new(
+                "slack",
+                AnyNotifier::Slack(SlackNotifier::new(url.into())),
+            )],
+        )
+    }
+
+    fn checkpoint() -> Checkpoint {
+        Checkpoint::new(Checkpoint::sync_name("test"), Utc::now())
+    }
+
+    #[tokio::test]
+    async fn deliver_pending_marks_delivered() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/").with_status(200).expect(1).create();
+
+        let routes = routes(server.url());
+        let outbox = MemoryRepository::default();
+        outbox
+            .enqueue_notifications(&routes.assign(&test_domains(3)), &checkpoint())
+            .await
+            .unwrap();
+
+        assert_eq!(deliver_pending(&outbox, &routes, 5, 3).await.unwrap(), 3);
+        assert_eq!(deliver_pending(&outbox, &routes, 5, 3).await.unwrap(), 0);
+        assert_eq!(*outbox.delivered.lock().unwrap(), vec![1, 2, 3]);
+        mock.assert();
+
+        let audit_log = outbox.audit_log.lock().unwrap();
+        assert_eq!(audit_log.len(), 1);
+        assert_eq!(audit_log[0].kind, AuditKind::Announce);
+        assert_eq!(
+            audit_log[0].target,
+            outbox.outbox.lock().unwrap()[0].notifier
+        );
+        assert_eq!(audit_log[0].domains.len(), 3);
+        assert_eq!(audit_log[0].receipt.status, Some(200));
+        assert!(audit_log[0].receipt.payload_hash.is_some());
+    }
+
+    #[tokio::test]
+    async fn deliver_pending_schedules_retry() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/").with_status(500).create();
+
+        let routes = routes(server.url());
+        let outbox = MemoryRepository::default();
+        outbox
+            .enqueue_notifications(&routes.assign(&test_domains(1)), &checkpoint())
+            .await
+            .unwrap();
+
+        assert_eq!(deliver_pending(&outbox, &routes, 5, 3).await.unwrap(), 0);
+
+        let failed = outbox.failed.lock().unwrap();
+        assert_eq!(failed.len(), 1);
+        assert!(failed[0]
+            .1
+            .is_some_and(|retry_at| retry_at > Utc::now().naive_utc()));
+        mock.assert();
+
+        let audit_log = outbox.audit_log.lock().unwrap();
+        assert_eq!(audit_log[0].receipt.status, Some(500));
+        assert_eq!(audit_log[0].error, None);
+    }
+
+    #[tokio::test]
+    async fn deliver_pending_gives_up_after_max_attempts() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/").with_status(500).create();
+
+        let routes = routes(server.url());
+        let outbox = MemoryRepository::default();
+        outbox
+            .enqueue_notifications(&routes.assign(&test_domains(1)), &checkpoint())
+            .await
+            .unwrap();
+        outbox.outbox.lock().unwrap()[0].attempts = 2;
+
+        deliver_pending(&outbox, &routes, 5, 3).await.unwrap();
+
+        assert_eq!(*outbox.failed.lock().unwrap(), vec![(1, None)]);
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn deliver_pending_fails_unknown_notifiers() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/").with_status(200).expect(1).create();
+
+        let routes = routes(server.url());
+        let outbox = MemoryRepository::default();
+        let mut notifications = routes.assign(&test_domains(1));
+        notifications.push(("removed".to_string(), test_domains(2).remove(1)));
+        outbox
+            .enqueue_notifications(&notifications, &checkpoint())
+            .await
+            .unwrap();
+
+        assert_eq!(deliver_pending(&outbox, &routes, 5, 3).await.unwrap(), 1);
+        assert_eq!(*outbox.delivered.lock().unwrap(), vec![1]);
+        assert_eq!(*outbox.failed.lock().unwrap(), vec![(2, None)]);
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn outbox_worker_delivers_due_messages_on_shutdown() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/").with_status(200).expect(1).create();
+
+        let routes = routes(server.url());
+        let outbox = MemoryRepository::default();
+        outbox
+            .enqueue_notifications(&routes.assign(&test_domains(1)), &checkpoint())
+            .await
+            .unwrap();
+
+        let (sender, shutdown) = Shutdown::channel();
+        sender.send_replace(true);
+
+        tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            run_outbox_worker(outbox, routes, 60, 3, shutdown),
+        )
+        .await
+        .expect("the worker stops on shutdown");
+
+        mock.assert();
+    }
+
+    #[test]
+    fn backoff_is_exponential_and_capped() {
+        assert_eq!(backoff(5, 1), 5);
+        assert_eq!(backoff(5, 2), 10);
+        assert_eq!(backoff(5, 4), 40);
+        assert_eq!(backoff(5, 100), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,289 @@
+use serde::Deserialize;
+
+use crate::{
+    models::registered_domain::RegisteredDomain,
+    notifiers::{conditions::Conditions, AnyNotifier, KeyedNotifier, Notifier},
+};
+
+static DEFAULT_ROUTE_NAME: &str = "default";
+
+/// Sends the domains with matching traits to its own notifiers.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Route {
+    pub name: String,
+    /// The traits a domain must have to take this route, e.g.
+    /// `{ length = ["SingleCharacter", "VeryShort"] }`.
+    #[serde(default)]
+    pub when: Conditions,
+    pub notifiers: Vec<KeyedNotifier>,
+}
+
+/// The routes of the announcements.
+///
+/// Every domain takes the first route whose `when` traits match. Domains that match
+/// none of the routes take the default route.
+#[derive(Debug, Clone)]
+pub struct Routes {
+    routes: Vec<Route>,
+    default: Route,
+}
+
+impl Routes {
+    pub fn new(routes: Vec<Route>, default_notifiers: Vec<KeyedNotifier>) -> Self {
+        Self {
+            routes,
+            default: Route {
+                name: DEFAULT_ROUTE_NAME.to_string(),
+                when: Conditions::default(),
+                notifiers: default_notifiers,
+            },
+        }
+    }
+
+    /// Returns the route taken by the domain.
+    pub fn route(&self, domain: &RegisteredDomain) -> &Route {
+        self.routes
+            .iter()
+            .find(|route| route.when.matches(domain))
+            .unwrap_or(&self.default)
+    }
+
+    /// Returns the notifiers of all routes.
+    pub fn notifiers(&self) -> impl Iterator<Item = &AnyNotifier> {
+        self.keyed().map(|keyed| &keyed.notifier)
+    }
+
+    /// Returns the notifiers of all routes that announce registrations by their id.
+    pub fn keyed_notifiers(&self) -> Vec<(String, &AnyNotifier)> {
+        self.keyed()
+            .filter(|keyed| keyed.notifier.announces_registrations())
+            .map(|keyed| (keyed.id.clone(), &keyed.notifier))
+            .collect()
+    }
+
+    /// Returns the announcements of the domains as pairs of the id of a notifier and
+    /// a domain routed to it.
+    pub fn assign(&self, domains: &[RegisteredDomain]) -> Vec<(String, RegisteredDomain)> {
+        domains
+            .iter()
+            .flat_map(|domain| {
+                self.route(domain)
+                    .notifiers
+                    .iter()
+                    .filter(|keyed| keyed.notifier.announces_registrations())
+                    .map(|keyed| (keyed.id.clone(), domain.clone()))
+            })
+            .collect()
+    }
+
+    fn keyed(&self) -> impl Iterator<Item = &KeyedNotifier> {
+        self.all().flat_map(|route| &route.notifiers)
+    }
+
+    fn all(&self) -> impl Iterator<Item = &Route> {
+        self.routes.iter().chain([&self.default])
+    }
+
+    /// Checks the names and conditions of the routes.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+
+        for (index, route) in self.routes.iter().enumerate() {
+            if route.name == DEFAULT_ROUTE_NAME
+                || self.routes[..index].iter().any(|r| r.name == route.name)
+            {
+                errors.push(format!("Route `{}` is defined more than once", route.name));
+            }
+
+            if route.notifiers.is_empty() {
+                errors.push(format!("Route `{}` has no notifiers", route.name));
+            }
+
+            if let Err(e) = route.when.validate() {
+                errors.push(format!("Route `{}`: {}", route.name, e));
+            }
+        }
+
+        let notifiers = self.keyed().collect::<Vec<_>>();
+        for (index, keyed) in notifiers.iter().enumerate() {
+            if keyed.id.trim().is_empty() {
+                errors.push("Every notifier must have an id".to_string());
+            } else if notifiers[..index].iter().any(|n| n.id == keyed.id) {
+                errors.push(format!("Notifier id `{}` is used more than once", keyed.id));
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifiers::{
+        email::{EmailRecipients, EmailTls},
+        EmailNotifier, SlackNotifier,
+    };
+
+    const ROUTES_TOML: &str = r#"
+        [[routes]]
+        name = "premium"
+        when = { length = ["SingleCharacter", "VeryShort"] }
+
+        [[routes.notifiers]]
+        id = "premium-slack"
+        type = "slack"
+        webhook_url = "https://hooks.slack.com/services/T000/B000/PREMIUM"
+
+        [[routes.notifiers]]
+        id = "premium-discord"
+        type = "discord"
+        webhook_url = "https://discord.com/api/webhooks/1234/premium"
+    "#;
+
+    fn routes() -> Routes {
+        #[derive(Deserialize)]
+        struct File {
+            routes: Vec<Route>,
+        }
+
+        let file: File = toml::from_str(ROUTES_TOML).unwrap();
+
+        Routes::new(
+            file.routes,
+            vec![KeyedNotifier::new(
+                "general",
+                AnyNotifier::Slack(SlackNotifier::new(
+                    "https://hooks.slack.com/services/T000/B000/GENERAL"
+                        .to_string()
+                        .into(),
+                )),
+            )],
+        )
+    }
+
+    fn domain(name: &str) -> RegisteredDomain {
+        RegisteredDomain::new(name.to_string(), "tx".to_string(), chrono::Utc::now())
+    }
+
+    #[test]
+    fn routes_route_by_traits() {
+        let routes = routes();
+
+        assert_eq!(routes.route(&domain("x.o")).name, "premium");
+        assert_eq!(routes.route(&domain("xi.o")).name, "premium");
+        assert_eq!(routes.route(&domain("xiler.o")).name, "default");
+    }
+
+    #[test]
+    fn routes_keyed_notifiers() {
+        let routes = routes();
+        let keys = routes
+            .keyed_notifiers()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+
+        assert_eq!(keys, vec!["premium-slack", "premium-discord", "general"]);
+
+        let names = routes.notifiers().map(|n| n.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["SLACK", "DISCORD", "SLACK"]);
+    }
+
+    #[test]
+    fn routes_skip_notifiers_without_registrations() {
+        let mut routes = routes();
+        let alerts_only = EmailNotifier::new(
+            "smtp.example.com".to_string(),
+            None,
+            EmailTls::Starttls,
+            "noreply@xiler.net".to_string(),
+            EmailRecipients {
+                registrations: vec![],
+                failures: vec!["ops@example.com".to_string()],
+            },
+        );
+        routes.default.notifiers.push(KeyedNotifier::new(
+            "ops-email",
+            AnyNotifier::Email(Box::new(alerts_only)),
+        ));
+
+        assert_eq!(routes.notifiers().count(), 4);
+        assert_eq!(routes.keyed_notifiers().len(), 3);
+        assert_eq!(routes.assign(&[domain("xiler.o")]).len(), 1);
+    }
+
+    #[test]
+    fn routes_keys_survive_reordering() {
+        let mut routes = routes();
+        routes.routes[0].notifiers.reverse();
+
+        let assigned = routes.assign(&[domain("x.o")]);
+
+        assert_eq!(assigned[0].0, "premium-discord");
+        assert_eq!(assigned[1].0, "premium-slack");
+    }
+
+    #[test]
+    fn routes_require_an_id() {
+        #[derive(Deserialize)]
+        struct File {
+            #[allow(dead_code)]
+            routes: Vec<Route>,
+        }
+
+        let error = toml::from_str::<File>(&ROUTES_TOML.replace("id = \"premium-slack\"", ""))
+            .err()
+            .unwrap()
+            .to_string();
+
+        assert!(error.contains("missing field `id`"));
+    }
+
+    #[test]
+    fn routes_assign() {
+        let routes = routes();
+        let domains = vec![domain("x.o"), domain("xiler.o")];
+
+        let assigned = routes
+            .assign(&domains)
+            .into_iter()
+            .map(|(key, domain)| (key, domain.domain))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            assigned,
+            vec![
+                ("premium-slack".to_string(), "x.o".to_string()),
+                ("premium-discord".to_string(), "x.o".to_string()),
+                ("general".to_string(), "xiler.o".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn routes_validate() {
+        assert!(routes().validate().is_empty());
+
+        let invalid = Routes::new(
+            vec![
+                Route {
+                    name: "default".to_string(),
+                    when: Conditions::default(),
+                    notifiers: vec![],
+                },
+                Route {
+                    name: "typo".to_string(),
+                    when: [("lenght", "Short")].into_iter().collect(),
+                    notifiers: routes().routes[0].notifiers.clone(),
+                },
+            ],
+            routes().routes[0].notifiers[..1].to_vec(),
+        );
+
+        let errors = invalid.validate();
+        assert_eq!(errors.len(), 4);
+        assert!(errors.contains(&"Notifier id `premium-slack` is used more than once".to_string()));
+    }
+}
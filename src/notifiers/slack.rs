@@ -0,0 +1,188 @@
+use bitcoin::Network;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    models::{audit_entry::Receipt, registered_domain::RegisteredDomain},
+    notifiers::{explorers::Explorers, mainnet, network_prefix, send, Notifier, NotifyError},
+    utils::secret::Secret,
+};
+
+/// Announces domains through a Slack incoming webhook, one section per domain.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackNotifier {
+    webhook_url: Secret<String>,
+    #[serde(skip, default = "mainnet")]
+    network: Network,
+    #[serde(skip)]
+    explorers: Explorers,
+    #[serde(skip)]
+    client: reqwest::Client,
+}
+
+impl SlackNotifier {
+    pub fn new(webhook_url: Secret<String>) -> Self {
+        Self {
+            webhook_url,
+            network: Network::Bitcoin,
+            explorers: Explorers::default(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Tags the announcements with the network, unless it is mainnet.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Links the announcements to the given explorers.
+    pub fn with_explorers(mut self, explorers: Explorers) -> Self {
+        self.explorers = explorers;
+        self
+    }
+}
+
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "SLACK"
+    }
+
+    /// Slack allows up to 50 blocks per message.
+    fn batch_size(&self) -> usize {
+        50
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match Url::parse(self.webhook_url.expose()) {
+            Ok(url)
+                if url.scheme() == "https"
+                    && url.host_str() == Some("hooks.slack.com")
+                    && url.path().starts_with("/services/") =>
+            {
+                Ok(())
+            }
+            Ok(_) => Err("Slack webhook_url is not a Slack incoming webhook URL".to_string()),
+            Err(e) => Err(format!("Slack webhook_url is not a valid URL: {}", e)),
+        }
+    }
+
+    async fn notify(&self, domains: &[RegisteredDomain]) -> Result<Receipt, NotifyError> {
+        let prefix = network_prefix(self.network);
+
+        let blocks = domains
+            .iter()
+            .map(|domain| {
+                json!({
+                    "type": "section",
+                    "text": {
+                        "type": "mrkdwn",
+                        "text": format!(
+                            "{}`{}` has just been registered!\n<{}|{}>",
+                            prefix,
+                            domain.domain,
+                            self.explorers.url(domain),
+                            domain.inscription_id
+                        )
+                    }
+                })
+            })
+            .collect::<Vec<serde_json::Value>>();
+
+        let text = domains
+            .iter()
+            .map(|domain| format!("{}{} has just been registered!", prefix, domain.domain))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let json = json!({
+            "text": text,
+            "blocks": blocks
+        });
+
+        send(self.client.post(self.webhook_url.expose()).json(&json)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifiers::test_domains;
+    use mockito::Matcher;
+
+    #[tokio::test]
+    async fn slack_notify() {
+        let mut server = mockito::Server::new();
+        let domains = test_domains(1);
+
+        let mock = server
+            .mock("POST", "/services/T000/B000/XXXX")
+            .match_header("content-type", "application/json")
+            .match_body(Matcher::PartialJson(json!({
+                "text": "domain0.o has just been registered!",
+                "blocks": [
+                    {
+                        "type": "section",
+                        "text": {
+                            "type": "mrkdwn",
+                            "text": format!(
+                                "`domain0.o` has just been registered!\n<https://ordinalswallet.com/inscription/{0}|{0}>",
+                                domains[0].inscription_id
+                            )
+                        }
+                    }
+                ]
+            })))
+            .with_status(200)
+            .create();
+
+        let notifier =
+            SlackNotifier::new(format!("{}/services/T000/B000/XXXX", server.url()).into());
+
+        assert!(notifier.notify(&domains).await.is_ok());
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn slack_notify_on_signet() {
+        let mut server = mockito::Server::new();
+        let domains = test_domains(1);
+
+        let mock = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "text": "[signet] domain0.o has just been registered!",
+                "blocks": [{
+                    "text": {
+                        "text": format!(
+                            "[signet] `domain0.o` has just been registered!\n<https://signet.ordinals.com/inscription/{0}|{0}>",
+                            domains[0].inscription_id
+                        )
+                    }
+                }]
+            })))
+            .with_status(200)
+            .create();
+
+        let notifier = SlackNotifier::new(server.url().into())
+            .with_network(Network::Signet)
+            .with_explorers(Explorers::defaults(Network::Signet));
+
+        assert!(notifier.notify(&domains).await.is_ok());
+        mock.assert();
+    }
+
+    #[test]
+    fn slack_validate() {
+        let valid = SlackNotifier::new(
+            "https://hooks.slack.com/services/T000/B000/XXXX"
+                .to_string()
+                .into(),
+        );
+        let invalid = SlackNotifier::new("https://example.com/services/T000".to_string().into());
+
+        assert!(valid.validate().is_ok());
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use bitcoin::Network;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    models::{audit_entry::Receipt, registered_domain::RegisteredDomain},
+    notifiers::{
+        escape_html, explorers::Explorers, mainnet, network_prefix, send, Notifier, NotifyError,
+    },
+    utils::secret::Secret,
+};
+
+static DEFAULT_API_URL: &str = "https://api.telegram.org";
+
+/// Announces domains to a Telegram chat through the Bot API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramNotifier {
+    bot_token: Secret<String>,
+    /// The chat id, or the `@username` of a public channel.
+    chat_id: String,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+    #[serde(skip, default = "mainnet")]
+    network: Network,
+    #[serde(skip)]
+    explorers: Explorers,
+    #[serde(skip)]
+    client: reqwest::Client,
+}
+
+fn default_api_url() -> String {
+    DEFAULT_API_URL.to_string()
+}
+
+impl TelegramNotifier {
+    pub fn new(bot_token: Secret<String>, chat_id: String) -> Self {
+        Self {
+            bot_token,
+            chat_id,
+            api_url: default_api_url(),
+            network: Network::Bitcoin,
+            explorers: Explorers::default(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Tags the announcements with the network, unless it is mainnet.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Links the announcements to the given explorers.
+    pub fn with_explorers(mut self, explorers: Explorers) -> Self {
+        self.explorers = explorers;
+        self
+    }
+
+    /// Overrides the Bot API URL, e.g. for a local Bot API server.
+    pub fn with_api_url(mut self, api_url: String) -> Self {
+        self.api_url = api_url;
+        self
+    }
+}
+
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &'static str {
+        "TELEGRAM"
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.bot_token.expose().is_empty() {
+            return Err("Telegram bot_token must not be empty".to_string());
+        }
+
+        if self.chat_id.is_empty() {
+            return Err("Telegram chat_id must not be empty".to_string());
+        }
+
+        match Url::parse(&self.api_url) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("Telegram api_url is not a valid URL: {}", e)),
+        }
+    }
+
+    async fn notify(&self, domains: &[RegisteredDomain]) -> Result<Receipt, NotifyError> {
+        let prefix = network_prefix(self.network);
+
+        let text = domains
+            .iter()
+            .map(|domain| {
+                format!(
+                    "{}<code>{}</code> has just been registered!\n<a href=\"{}\">{}</a>",
+                    prefix,
+                    escape_html(&domain.domain),
+                    self.explorers.url(domain),
+                    domain.inscription_id
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let json = json!({
+            "chat_id": self.chat_id,
+            "text": text,
+            "parse_mode": "HTML",
+            "disable_web_page_preview": true
+        });
+
+        let url = format!(
+            "{}/bot{}/sendMessage",
+            self.api_url.trim_end_matches('/'),
+            self.bot_token.expose()
+        );
+
+        send(self.client.post(url).json(&json)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifiers::test_domains;
+    use mockito::Matcher;
+
+    #[tokio::test]
+    async fn telegram_notify() {
+        let mut server = mockito::Server::new();
+        let domains = test_domains(2);
+
+        let mock = server
+            .mock("POST", "/bot1234:token/sendMessage")
+            .match_header("content-type", "application/json")
+            .match_body(Matcher::Json(json!({
+                "chat_id": "@xiler",
+                "text": format!(
+                    "<code>domain0.o</code> has just been registered!\n<a href=\"https://ordinalswallet.com/inscription/{0}\">{0}</a>\n\n<code>domain1.o</code> has just been registered!\n<a href=\"https://ordinalswallet.com/inscription/{0}\">{0}</a>",
+                    domains[0].inscription_id
+                ),
+                "parse_mode": "HTML",
+                "disable_web_page_preview": true
+            })))
+            .with_status(200)
+            .create();
+
+        let notifier = TelegramNotifier::new("1234:token".to_string().into(), "@xiler".to_string())
+            .with_api_url(server.url());
+
+        assert!(notifier.notify(&domains).await.is_ok());
+        mock.assert();
+    }
+
+    #[test]
+    fn telegram_validate() {
+        let valid = TelegramNotifier::new("1234:token".to_string().into(), "@xiler".to_string());
+        let invalid = TelegramNotifier::new("1234:token".to_string().into(), String::new());
+
+        assert!(valid.validate().is_ok());
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn telegram_escape_html() {
+        assert_eq!(escape_html("<a&b>"), "&lt;a&amp;b&gt;");
+        assert_eq!(escape_html("\"a\""), "&quot;a&quot;");
+    }
+}
@@ -0,0 +1,442 @@
+use std::{fs, path::Path, sync::Arc};
+
+use color_eyre::eyre::{bail, Result, WrapErr};
+use handlebars::{no_escape, Handlebars};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::error;
+
+use crate::{
+    models::registered_domain::RegisteredDomain,
+    notifiers::{conditions::Conditions, explorers::Explorers},
+};
+
+pub const DEFAULT_COLOR: u32 = 3512539;
+static DEFAULT_TEMPLATE_NAME: &str = "default";
+static DEFAULT_DESCRIPTION: &str =
+    "`{{domain}}` has just been registered!\n[{{inscription_id}}]({{inscription_url}})";
+static DEFAULT_LINKS_NAME: &str = "Explorers";
+static DEFAULT_LINKS_VALUE: &str =
+    "{{#each links}}[{{name}}]({{url}}){{#unless @last}} · {{/unless}}{{/each}}";
+
+/// An announcement template, rendered once per registered domain.
+///
+/// Every text is a Handlebars template with access to `domain`, `name` (the domain
+/// without `.o`), `inscription_id`, `inscription_url` (the link of the first
+/// explorer), `links` (a `name` and `url` per explorer), `reveal_tx`, `length`,
+/// `traits` (e.g. `traits.length`), `registered_at` (RFC 3339) and
+/// `registered_at_unix`. Fields that render empty are left out.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    pub name: String,
+    /// The traits a domain must have for the template to be used, e.g.
+    /// `{ length = "SingleCharacter" }`. Matches every domain when empty.
+    #[serde(default)]
+    pub when: Conditions,
+    pub title: Option<String>,
+    pub description: String,
+    #[serde(default = "default_color")]
+    pub color: u32,
+    pub thumbnail: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<TemplateField>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateField {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub inline: bool,
+}
+
+fn default_color() -> u32 {
+    DEFAULT_COLOR
+}
+
+/// A rendered announcement of a single domain.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Announcement {
+    pub title: Option<String>,
+    pub description: String,
+    pub color: u32,
+    pub thumbnail: Option<String>,
+    pub fields: Vec<AnnouncementField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnnouncementField {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TemplatesFile {
+    templates: Vec<Template>,
+}
+
+/// A set of announcement templates.
+///
+/// The first template whose `when` traits match a domain is used for it. If none
+/// match, the built-in default template is used.
+#[derive(Debug, Clone)]
+pub struct Templates {
+    templates: Vec<Template>,
+    registry: Arc<Handlebars<'static>>,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Self::new(vec![]).expect("the default template is valid")
+    }
+}
+
+impl Templates {
+    /// Compiles the templates, the built-in default template is appended as fallback.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a template name is used twice or a template does not compile.
+    pub fn new(mut templates: Vec<Template>) -> Result<Self> {
+        templates.push(Template {
+            name: DEFAULT_TEMPLATE_NAME.to_string(),
+            when: Conditions::default(),
+            title: None,
+            description: DEFAULT_DESCRIPTION.to_string(),
+            color: DEFAULT_COLOR,
+            thumbnail: None,
+            fields: vec![TemplateField {
+                name: DEFAULT_LINKS_NAME.to_string(),
+                value: DEFAULT_LINKS_VALUE.to_string(),
+                inline: false,
+            }],
+        });
+
+        let mut registry = Handlebars::new();
+        registry.register_escape_fn(no_escape);
+
+        for (index, template) in templates.iter().enumerate() {
+            if templates[..index].iter().any(|t| t.name == template.name) {
+                bail!("Template `{}` is defined more than once", template.name);
+            }
+
+            if let Err(e) = template.when.validate() {
+                bail!("Template `{}`: {}", template.name, e);
+            }
+
+            let mut register = |part: String, source: &str| {
+                registry
+                    .register_template_string(&part, source)
+                    .wrap_err_with(|| format!("Invalid template {}", part))
+            };
+
+            if let Some(title) = &template.title {
+                register(format!("{}.title", template.name), title)?;
+            }
+
+            if let Some(thumbnail) = &template.thumbnail {
+                register(format!("{}.thumbnail", template.name), thumbnail)?;
+            }
+
+            register(
+                format!("{}.description", template.name),
+                &template.description,
+            )?;
+
+            for (i, field) in template.fields.iter().enumerate() {
+                register(format!("{}.fields.{}.name", template.name, i), &field.name)?;
+                register(
+                    format!("{}.fields.{}.value", template.name, i),
+                    &field.value,
+                )?;
+            }
+        }
+
+        Ok(Self {
+            templates,
+            registry: Arc::new(registry),
+        })
+    }
+
+    /// Loads the templates from a TOML file with a `[[templates]]` list.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read templates file {}", path.display()))?;
+        let file: TemplatesFile = toml::from_str(&contents)
+            .wrap_err_with(|| format!("Failed to parse templates file {}", path.display()))?;
+
+        Self::new(file.templates)
+            .wrap_err_with(|| format!("Invalid templates file {}", path.display()))
+    }
+
+    /// Returns the first template matching the traits of the domain.
+    pub fn select(&self, domain: &RegisteredDomain) -> &Template {
+        self.templates
+            .iter()
+            .find(|template| template.when.matches(domain))
+            .expect("the default template matches every domain")
+    }
+
+    /// Renders the announcement of a domain, linking to the given explorers.
+    ///
+    /// Falls back to the default template if the selected template fails to render.
+    pub fn render(&self, domain: &RegisteredDomain, explorers: &Explorers) -> Announcement {
+        let template = self.select(domain);
+        let context = context(domain, explorers);
+
+        self.render_template(template, &context)
+            .unwrap_or_else(|e| {
+                error!("[TEMPLATES] Failed to render `{}`: {}", template.name, e);
+
+                let default = self.templates.last().expect("the default template exists");
+                self.render_template(default, &context)
+                    .expect("the default template renders")
+            })
+    }
+
+    fn render_template(
+        &self,
+        template: &Template,
+        context: &serde_json::Value,
+    ) -> Result<Announcement, handlebars::RenderError> {
+        let render = |part: String| self.registry.render(&part, context);
+
+        let title = match template.title {
+            Some(_) => Some(render(format!("{}.title", template.name))?),
+            None => None,
+        };
+
+        let thumbnail = match template.thumbnail {
+            Some(_) => Some(render(format!("{}.thumbnail", template.name))?),
+            None => None,
+        };
+
+        let fields = template
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                Ok(AnnouncementField {
+                    name: render(format!("{}.fields.{}.name", template.name, i))?,
+                    value: render(format!("{}.fields.{}.value", template.name, i))?,
+                    inline: field.inline,
+                })
+            })
+            .collect::<Result<Vec<_>, handlebars::RenderError>>()?
+            .into_iter()
+            .filter(|field| !field.name.is_empty() && !field.value.is_empty())
+            .collect();
+
+        Ok(Announcement {
+            title,
+            description: render(format!("{}.description", template.name))?,
+            color: template.color,
+            thumbnail,
+            fields,
+        })
+    }
+}
+
+fn context(domain: &RegisteredDomain, explorers: &Explorers) -> serde_json::Value {
+    json!({
+        "domain": domain.domain,
+        "name": domain.domain.trim_end_matches(".o"),
+        "inscription_id": domain.inscription_id,
+        "inscription_url": explorers.url(domain),
+        "links": explorers.links(domain),
+        "reveal_tx": domain.reveal_tx,
+        "length": domain.length().to_string(),
+        "traits": domain.traits(),
+        "registered_at": domain.registered_at.to_rfc3339(),
+        "registered_at_unix": domain.registered_at.timestamp(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifiers::test_domains;
+
+    const TEMPLATES_TOML: &str = r#"
+        [[templates]]
+        name = "single-character"
+        when = { length = "SingleCharacter" }
+        title = "Single character drop: {{domain}}"
+        description = "`{{name}}` ({{traits.length}}) registered <t:{{registered_at_unix}}:R>"
+        color = 16766720
+        thumbnail = "https://xiler.net/thumbnails/{{name}}.png"
+        fields = [
+            { name = "Inscription", value = "[{{inscription_id}}]({{inscription_url}})" },
+            { name = "Length", value = "{{length}}", inline = true },
+        ]
+
+        [[templates]]
+        name = "short"
+        when = { length = "Short" }
+        description = "`{{domain}}` is short!"
+    "#;
+
+    fn templates() -> Templates {
+        let file: TemplatesFile = toml::from_str(TEMPLATES_TOML).unwrap();
+        Templates::new(file.templates).unwrap()
+    }
+
+    fn domain(name: &str) -> RegisteredDomain {
+        let domain = test_domains(1).remove(0);
+        RegisteredDomain::new(name.to_string(), domain.reveal_tx, domain.registered_at)
+    }
+
+    #[test]
+    fn templates_default_links_explorers() {
+        let domain = domain("xiler.o");
+        let announcement = Templates::default().render(&domain, &Explorers::default());
+
+        assert_eq!(
+            announcement,
+            Announcement {
+                title: None,
+                description: format!(
+                    "`xiler.o` has just been registered!\n[{0}](https://ordinalswallet.com/inscription/{0})",
+                    domain.inscription_id
+                ),
+                color: DEFAULT_COLOR,
+                thumbnail: None,
+                fields: vec![AnnouncementField {
+                    name: "Explorers".to_string(),
+                    value: format!(
+                        "[OrdinalsWallet](https://ordinalswallet.com/inscription/{0}) · \
+                         [Ordinals](https://ordinals.com/inscription/{0}) · \
+                         [Mempool](https://mempool.space/tx/{1})",
+                        domain.inscription_id, domain.reveal_tx
+                    ),
+                    inline: false,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn templates_skip_empty_fields() {
+        let template: Template = toml::from_str(
+            r#"
+                name = "all"
+                description = "{{domain}}"
+                fields = [
+                    { name = "Mempool", value = "{{#each links}}{{#if (eq name \"Mempool\")}}{{url}}{{/if}}{{/each}}" },
+                ]
+            "#,
+        )
+        .unwrap();
+        let templates = Templates::new(vec![template]).unwrap();
+        let domain = domain("xiler.o");
+
+        let mainnet = templates.render(&domain, &Explorers::default());
+        let regtest = templates.render(&domain, &Explorers::defaults(bitcoin::Network::Regtest));
+
+        assert_eq!(mainnet.fields.len(), 1);
+        assert!(regtest.fields.is_empty());
+    }
+
+    #[test]
+    fn templates_select_by_traits() {
+        let templates = templates();
+
+        assert_eq!(templates.select(&domain("x.o")).name, "single-character");
+        assert_eq!(templates.select(&domain("xil.o")).name, "short");
+        assert_eq!(templates.select(&domain("xiler.o")).name, "default");
+    }
+
+    #[test]
+    fn templates_render_all_parts() {
+        let domain = domain("x.o");
+        let announcement = templates().render(&domain, &Explorers::default());
+
+        assert_eq!(
+            announcement.title.as_deref(),
+            Some("Single character drop: x.o")
+        );
+        assert_eq!(
+            announcement.description,
+            "`x` (SingleCharacter) registered <t:1698000000:R>"
+        );
+        assert_eq!(announcement.color, 16766720);
+        assert_eq!(
+            announcement.thumbnail.as_deref(),
+            Some("https://xiler.net/thumbnails/x.png")
+        );
+        assert_eq!(
+            announcement.fields,
+            vec![
+                AnnouncementField {
+                    name: "Inscription".to_string(),
+                    value: format!(
+                        "[{0}](https://ordinalswallet.com/inscription/{0})",
+                        domain.inscription_id
+                    ),
+                    inline: false,
+                },
+                AnnouncementField {
+                    name: "Length".to_string(),
+                    value: "SingleCharacter".to_string(),
+                    inline: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn templates_load_example() {
+        let templates = Templates::load(Path::new("templates.example.toml")).unwrap();
+
+        assert_eq!(templates.select(&domain("x.o")).name, "single-character");
+    }
+
+    #[test]
+    fn templates_duplicate_name() {
+        let template = Template {
+            name: "default".to_string(),
+            when: Conditions::default(),
+            title: None,
+            description: "{{domain}}".to_string(),
+            color: DEFAULT_COLOR,
+            thumbnail: None,
+            fields: vec![],
+        };
+
+        assert!(Templates::new(vec![template]).is_err());
+    }
+
+    #[test]
+    fn templates_unknown_trait() {
+        let template = Template {
+            name: "typo".to_string(),
+            when: [("lenght", "Short")].into_iter().collect(),
+            title: None,
+            description: "{{domain}}".to_string(),
+            color: DEFAULT_COLOR,
+            thumbnail: None,
+            fields: vec![],
+        };
+
+        assert!(Templates::new(vec![template]).is_err());
+    }
+
+    #[test]
+    fn templates_invalid_syntax() {
+        let template = Template {
+            name: "broken".to_string(),
+            when: Conditions::default(),
+            title: None,
+            description: "{{#if domain}}".to_string(),
+            color: DEFAULT_COLOR,
+            thumbnail: None,
+            fields: vec![],
+        };
+
+        assert!(Templates::new(vec![template]).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use bitcoin::Network;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    models::{audit_entry::Receipt, registered_domain::RegisteredDomain},
+    notifiers::{
+        explorers::{Explorers, Link},
+        mainnet, network_name, send, Notifier, NotifyError,
+    },
+    utils::secret::Secret,
+};
+
+/// The version of the event payload, increased on breaking changes.
+pub const EVENT_VERSION: u32 = 1;
+pub static EVENT_TYPE: &str = "domains.registered";
+
+pub static EVENT_HEADER: &str = "X-Xiler-Event";
+pub static TIMESTAMP_HEADER: &str = "X-Xiler-Timestamp";
+pub static SIGNATURE_HEADER: &str = "X-Xiler-Signature";
+
+/// Sends the registrations as a signed JSON event to any HTTP endpoint.
+///
+/// Every request is signed with HMAC-SHA256 over `{timestamp}.{body}`, see
+/// [`verify_signature`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookNotifier {
+    url: String,
+    secret: Secret<String>,
+    #[serde(skip, default = "mainnet")]
+    network: Network,
+    #[serde(skip)]
+    explorers: Explorers,
+    #[serde(skip)]
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Serialize)]
+struct RegisteredDomainEvent<'a> {
+    domain: &'a str,
+    inscription_id: &'a str,
+    inscription_url: String,
+    links: Vec<Link>,
+    reveal_tx: &'a str,
+    length: String,
+    traits: std::collections::HashMap<String, String>,
+    registered_at: DateTime<Utc>,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String, secret: Secret<String>) -> Self {
+        Self {
+            url,
+            secret,
+            network: Network::Bitcoin,
+            explorers: Explorers::default(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Tags the announcements with the network, unless it is mainnet.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Links the announcements to the given explorers.
+    pub fn with_explorers(mut self, explorers: Explorers) -> Self {
+        self.explorers = explorers;
+        self
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "WEBHOOK"
+    }
+
+    fn batch_size(&self) -> usize {
+        100
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match Url::parse(&self.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => return Err("Webhook url must be a http(s) URL".to_string()),
+            Err(e) => return Err(format!("Webhook url is not a valid URL: {}", e)),
+        }
+
+        if self.secret.expose().len() < 16 {
+            return Err("Webhook secret must be at least 16 characters".to_string());
+        }
+
+        Ok(())
+    }
+
+    async fn notify(&self, domains: &[RegisteredDomain]) -> Result<Receipt, NotifyError> {
+        let timestamp = Utc::now().timestamp();
+        let body = event(domains, self.network, &self.explorers, timestamp).to_string();
+        let signature = sign(self.secret.expose(), timestamp, &body);
+
+        send(
+            self.client
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, EVENT_TYPE)
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(SIGNATURE_HEADER, signature)
+                .body(body),
+        )
+        .await
+    }
+}
+
+/// Returns the event announcing the domains.
+///
+/// The id of the event only depends on the domains, so receivers can use it to
+/// ignore retried deliveries.
+fn event(
+    domains: &[RegisteredDomain],
+    network: Network,
+    explorers: &Explorers,
+    timestamp: i64,
+) -> serde_json::Value {
+    let mut id = Sha256::new();
+    for domain in domains {
+        id.update(domain.inscription_id.as_bytes());
+    }
+
+    let domains = domains
+        .iter()
+        .map(|domain| RegisteredDomainEvent {
+            domain: &domain.domain,
+            inscription_id: &domain.inscription_id,
+            inscription_url: explorers.url(domain),
+            links: explorers.links(domain),
+            reveal_tx: &domain.reveal_tx,
+            length: domain.length().to_string(),
+            traits: domain.traits(),
+            registered_at: domain.registered_at,
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "version": EVENT_VERSION,
+        "type": EVENT_TYPE,
+        "id": hex::encode(id.finalize()),
+        "timestamp": timestamp,
+        "network": network_name(network),
+        "data": {
+            "domains": domains
+        }
+    })
+}
+
+/// Returns the signature header of a request, `sha256=` followed by the hex encoded
+/// HMAC-SHA256 of `{timestamp}.{body}`.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Verifies a request of the webhook notifier, for use by receivers.
+///
+/// # Arguments
+///
+/// * `secret` - The shared secret of the webhook.
+/// * `timestamp` - The value of the `X-Xiler-Timestamp` header.
+/// * `body` - The raw request body.
+/// * `signature` - The value of the `X-Xiler-Signature` header.
+/// * `tolerance` - The maximum age of the request in seconds, protecting against replays.
+///
+/// # Errors
+///
+/// Returns an error if the signature does not match or the request is too old.
+pub fn verify_signature(
+    secret: &str,
+    timestamp: &str,
+    body: &str,
+    signature: &str,
+    tolerance: i64,
+) -> Result<(), String> {
+    let timestamp: i64 = timestamp
+        .parse()
+        .map_err(|_| "The timestamp is not a unix timestamp".to_string())?;
+
+    if (Utc::now().timestamp() - timestamp).abs() > tolerance {
+        return Err("The timestamp is outside of the tolerance".to_string());
+    }
+
+    let signature = signature
+        .strip_prefix("sha256=")
+        .and_then(|signature| hex::decode(signature).ok())
+        .ok_or_else(|| "The signature is not a sha256= hex signature".to_string())?;
+
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+
+    // Constant time comparison.
+    mac.verify_slice(&signature)
+        .map_err(|_| "The signature does not match".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifiers::test_domains;
+    use mockito::Matcher;
+    use std::sync::{Arc, Mutex};
+
+    const SECRET: &str = "0123456789abcdef";
+
+    #[test]
+    fn webhook_sign_known_value() {
+        // echo -n '1698000000.{}' | openssl dgst -sha256 -hmac 0123456789abcdef
+        assert_eq!(
+            sign(SECRET, 1698000000, "{}"),
+            "sha256=6b1ef8551f70abced1400cbf59e0b927405b76bbae8e3ac192e708bf495424b6"
+        );
+    }
+
+    #[test]
+    fn webhook_verify_signature() {
+        let timestamp = Utc::now().timestamp();
+        let signature = sign(SECRET, timestamp, "{}");
+        let timestamp = timestamp.to_string();
+
+        assert!(verify_signature(SECRET, &timestamp, "{}", &signature, 300).is_ok());
+        assert!(verify_signature("other secret", &timestamp, "{}", &signature, 300).is_err());
+        assert!(verify_signature(SECRET, &timestamp, "{ }", &signature, 300).is_err());
+        assert!(verify_signature(SECRET, &timestamp, "{}", "sha256=zz", 300).is_err());
+    }
+
+    #[test]
+    fn webhook_verify_signature_too_old() {
+        let timestamp = Utc::now().timestamp() - 600;
+        let signature = sign(SECRET, timestamp, "{}");
+
+        assert!(verify_signature(SECRET, &timestamp.to_string(), "{}", &signature, 300).is_err());
+    }
+
+    #[test]
+    fn webhook_event_id_is_stable() {
+        let domains = test_domains(2);
+        let explorers = Explorers::default();
+
+        assert_eq!(
+            event(&domains, Network::Bitcoin, &explorers, 1)["id"],
+            event(&domains, Network::Bitcoin, &explorers, 2)["id"]
+        );
+        assert_ne!(
+            event(&domains, Network::Bitcoin, &explorers, 1)["id"],
+            event(&domains[..1], Network::Bitcoin, &explorers, 1)["id"]
+        );
+    }
+
+    #[tokio::test]
+    async fn webhook_notify() {
+        let mut server = mockito::Server::new();
+        let domains = test_domains(1);
+        let request = Arc::new(Mutex::new(None));
+        let captured = request.clone();
+
+        let mock = server
+            .mock("POST", "/events")
+            .match_header("content-type", "application/json")
+            .match_header("x-xiler-event", EVENT_TYPE)
+            .match_body(Matcher::PartialJson(json!({
+                "version": EVENT_VERSION,
+                "type": EVENT_TYPE,
+                "data": {
+                    "domains": [{
+                        "domain": "domain0.o",
+                        "inscription_id": domains[0].inscription_id,
+                        "length": "Normal",
+                        "traits": { "length": "Normal", "charset": "Alphanumeric" },
+                        "registered_at": "2023-10-22T18:40:00Z"
+                    }]
+                }
+            })))
+            .with_status(200)
+            .with_body_from_request(move |request| {
+                let header = |name: &str| request.header(name)[0].to_string();
+
+                *captured.lock().unwrap() = Some((
+                    header(TIMESTAMP_HEADER),
+                    header(SIGNATURE_HEADER),
+                    String::from_utf8(request.body().unwrap().clone()).unwrap(),
+                ));
+                vec![]
+            })
+            .create();
+
+        let notifier = WebhookNotifier::new(
+            format!("{}/events", server.url()),
+            SECRET.to_string().into(),
+        );
+        assert!(notifier.notify(&domains).await.is_ok());
+        mock.assert();
+
+        let (timestamp, signature, body) = request.lock().unwrap().take().unwrap();
+        assert!(verify_signature(SECRET, &timestamp, &body, &signature, 300).is_ok());
+    }
+
+    #[test]
+    fn webhook_validate() {
+        let valid = WebhookNotifier::new(
+            "https://xiler.net/hooks".to_string(),
+            SECRET.to_string().into(),
+        );
+        let short_secret = WebhookNotifier::new(
+            "https://xiler.net/hooks".to_string(),
+            "short".to_string().into(),
+        );
+
+        assert!(valid.validate().is_ok());
+        assert!(short_secret.validate().is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use std::{sync::RwLock, time::Duration as StdDuration};
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::{
+    config::Config,
+    db::leader::{current_leader, instance_name},
+    server::metrics::metrics,
+};
+
+use super::AppState;
+
+/// How long the database may take to answer a query of the readiness check.
+const DATABASE_TIMEOUT: StdDuration = StdDuration::from_secs(3);
+
+/// The status of the service or one of its components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Failing,
+}
+
+/// A component of the readiness report, with its status and details.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Component {
+    pub status: Status,
+    #[serde(flatten)]
+    pub details: Value,
+}
+
+impl Component {
+    fn ok(details: Value) -> Self {
+        Self {
+            status: Status::Ok,
+            details,
+        }
+    }
+
+    fn failing(details: Value) -> Self {
+        Self {
+            status: Status::Failing,
+            details,
+        }
+    }
+}
+
+/// The readiness report of `/readyz`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Readiness {
+    pub status: Status,
+    pub database: Component,
+    pub publish: Component,
+    /// Informational, a standby is as ready as the leader.
+    pub leader: Component,
+}
+
+/// Tracks the state the readiness of the service depends on.
+pub struct Health {
+    pool: PgPool,
+    slug: String,
+    instance: String,
+    /// How long domains may be pending before publishing counts as stalled.
+    stalled_after: Duration,
+    started_at: DateTime<Utc>,
+    last_publish: RwLock<Option<DateTime<Utc>>>,
+}
+
+impl Health {
+    pub fn new(pool: PgPool, config: &Config) -> Self {
+        Self {
+            pool,
+            slug: config.slug.clone(),
+            instance: instance_name(),
+            stalled_after: Duration::minutes(config.ops_alert_after as i64),
+            started_at: Utc::now(),
+            last_publish: RwLock::new(None),
+        }
+    }
+
+    /// Records a successful publish of the pending domains.
+    pub fn published(&self) {
+        *self.last_publish.write().unwrap() = Some(Utc::now());
+    }
+
+    /// Checks every component, the service is ready when none of them is failing.
+    pub async fn readiness(&self) -> Readiness {
+        let (database, leader) = tokio::join!(self.database(), self.leader());
+        let publish = self.publish(metrics().pending_domains.get(), Utc::now());
+
+        let status = if database.status == Status::Ok && publish.status == Status::Ok {
+            Status::Ok
+        } else {
+            Status::Failing
+        };
+
+        Readiness {
+            status,
+            database,
+            publish,
+            leader,
+        }
+    }
+
+    async fn database(&self) -> Component {
+        let query = sqlx::query("SELECT 1").execute(&self.pool);
+
+        match tokio::time::timeout(DATABASE_TIMEOUT, query).await {
+            Ok(Ok(_)) => Component::ok(json!({})),
+            Ok(Err(e)) => Component::failing(json!({ "error": e.to_string() })),
+            Err(_) => Component::failing(json!({
+                "error": format!("no answer within {} seconds", DATABASE_TIMEOUT.as_secs())
+            })),
+        }
+    }
+
+    /// Publishing is stalled when domains are pending for longer than `stalled_after`
+    /// since the last successful publish, or since startup.
+    fn publish(&self, pending_domains: i64, now: DateTime<Utc>) -> Component {
+        let last_publish = *self.last_publish.read().unwrap();
+        let age = last_publish.map(|last_publish| (now - last_publish).num_seconds());
+        let details = json!({
+            "last_publish": last_publish.map(|last_publish| last_publish.to_rfc3339()),
+            "age_seconds": age,
+            "pending_domains": pending_domains,
+            "poll_interval_seconds": metrics().poll_interval.get(),
+        });
+
+        let since = last_publish.unwrap_or(self.started_at);
+        if pending_domains > 0 && now - since > self.stalled_after {
+            Component::failing(details)
+        } else {
+            Component::ok(details)
+        }
+    }
+
+    async fn leader(&self) -> Component {
+        let current_leader =
+            tokio::time::timeout(DATABASE_TIMEOUT, current_leader(&self.pool, &self.slug))
+                .await
+                .ok()
+                .and_then(Result::ok)
+                .flatten();
+
+        Component::ok(json!({
+            "leader": metrics().leader.get() == 1,
+            "instance": self.instance,
+            "current_leader": current_leader,
+        }))
+    }
+}
+
+/// The process is up and serving requests.
+pub async fn get_healthz() -> Json<Value> {
+    Json(json!({ "status": Status::Ok }))
+}
+
+/// The service can sync, with a report of every component.
+pub async fn get_readyz(State(state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let readiness = state.health.readiness().await;
+
+    let code = match readiness.status {
+        Status::Ok => StatusCode::OK,
+        Status::Failing => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (code, Json(readiness))
+}
+
+#[cfg(test)]
+pub(crate) fn test_health() -> Health {
+    use crate::config::test_config;
+
+    // Nothing listens on port 1, so the database is unreachable. Creating the pool
+    // needs a runtime, even though it only connects on use.
+    let pool = PgPool::connect_lazy("postgresql://127.0.0.1:1/xiler").unwrap();
+
+    Health::new(pool, &test_config())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn health_publish_stalled() {
+        let health = test_health();
+        let later = health.started_at + health.stalled_after + Duration::seconds(1);
+
+        assert_eq!(health.publish(0, later).status, Status::Ok);
+        assert_eq!(health.publish(2, health.started_at).status, Status::Ok);
+        assert_eq!(health.publish(2, later).status, Status::Failing);
+
+        health.published();
+        let publish = health.publish(2, Utc::now());
+        assert_eq!(publish.status, Status::Ok);
+        assert_eq!(publish.details["age_seconds"], 0);
+        assert_eq!(publish.details["pending_domains"], 2);
+    }
+}
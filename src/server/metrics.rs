@@ -0,0 +1,214 @@
+use std::sync::LazyLock;
+
+use chrono::Utc;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// The prefix of every metric name.
+static NAMESPACE: &str = "xiler_sync";
+
+/// The buckets of the latency histograms, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Returns the metrics of the service.
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// The kinds of errors counted by `xiler_sync_errors_total`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Reading the registered domains failed.
+    Database,
+    /// Publishing the inscriptions to OrdinalsWallet failed.
+    Publish,
+    /// Reading or writing the notification outbox failed.
+    Outbox,
+    /// Sending an announcement failed.
+    Notify,
+    /// A malformed domain was quarantined.
+    Quarantine,
+    /// Announcements were queued for a notifier that is no longer configured.
+    UnknownNotifier,
+}
+
+impl ErrorKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Database => "database",
+            Self::Publish => "publish",
+            Self::Outbox => "outbox",
+            Self::Notify => "notify",
+            Self::Quarantine => "quarantine",
+            Self::UnknownNotifier => "unknown_notifier",
+        }
+    }
+}
+
+/// The Prometheus metrics of the sync loop and the announcements.
+pub struct Metrics {
+    registry: Registry,
+    pub domains_fetched: IntCounter,
+    pub domains_published: IntCounter,
+    /// Labeled by notifier.
+    pub domains_announced: IntCounterVec,
+    pub publish_duration: Histogram,
+    /// Labeled by notifier.
+    pub notify_duration: HistogramVec,
+    errors: IntCounterVec,
+    checkpoint: IntGauge,
+    checkpoint_lag: IntGauge,
+    pub pending_domains: IntGauge,
+    /// 1 while this instance holds the leader lock and syncs, 0 on standby.
+    pub leader: IntGauge,
+    /// The current amount of seconds between polls, which changes with adaptive polling.
+    pub poll_interval: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some(NAMESPACE.to_string()), None)
+            .expect("the namespace is valid");
+
+        let metrics = Self {
+            domains_fetched: IntCounter::new(
+                "domains_fetched_total",
+                "Registered domains read from the database",
+            )
+            .unwrap(),
+            domains_published: IntCounter::new(
+                "domains_published_total",
+                "Domains published to OrdinalsWallet",
+            )
+            .unwrap(),
+            domains_announced: IntCounterVec::new(
+                Opts::new("domains_announced_total", "Domains announced by a notifier"),
+                &["notifier"],
+            )
+            .unwrap(),
+            publish_duration: Histogram::with_opts(
+                HistogramOpts::new(
+                    "publish_duration_seconds",
+                    "Latency of publishing inscriptions to OrdinalsWallet",
+                )
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            )
+            .unwrap(),
+            notify_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "webhook_duration_seconds",
+                    "Latency of sending a batch of announcements",
+                )
+                .buckets(LATENCY_BUCKETS.to_vec()),
+                &["notifier"],
+            )
+            .unwrap(),
+            errors: IntCounterVec::new(Opts::new("errors_total", "Errors by kind"), &["kind"])
+                .unwrap(),
+            checkpoint: IntGauge::new(
+                "checkpoint_timestamp_seconds",
+                "Unix timestamp of the last update checkpoint",
+            )
+            .unwrap(),
+            checkpoint_lag: IntGauge::new(
+                "checkpoint_lag_seconds",
+                "Seconds between the last update checkpoint and now",
+            )
+            .unwrap(),
+            pending_domains: IntGauge::new(
+                "pending_domains",
+                "Domains registered since the checkpoint that are not published yet",
+            )
+            .unwrap(),
+            leader: IntGauge::new(
+                "leader",
+                "Whether this instance is the leader that syncs the domains",
+            )
+            .unwrap(),
+            poll_interval: IntGauge::new(
+                "poll_interval_seconds",
+                "Seconds between polls for new domains",
+            )
+            .unwrap(),
+            registry,
+        };
+
+        for collector in [
+            Box::new(metrics.domains_fetched.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(metrics.domains_published.clone()),
+            Box::new(metrics.domains_announced.clone()),
+            Box::new(metrics.publish_duration.clone()),
+            Box::new(metrics.notify_duration.clone()),
+            Box::new(metrics.errors.clone()),
+            Box::new(metrics.checkpoint.clone()),
+            Box::new(metrics.checkpoint_lag.clone()),
+            Box::new(metrics.pending_domains.clone()),
+            Box::new(metrics.leader.clone()),
+            Box::new(metrics.poll_interval.clone()),
+        ] {
+            metrics
+                .registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+
+        metrics
+    }
+
+    /// Counts an error of the given kind.
+    pub fn error(&self, kind: ErrorKind) {
+        self.errors.with_label_values(&[kind.as_str()]).inc();
+    }
+
+    /// Sets the time of the last update checkpoint.
+    pub fn set_checkpoint(&self, checkpoint: chrono::DateTime<Utc>) {
+        self.checkpoint.set(checkpoint.timestamp());
+    }
+
+    /// Returns the metrics in the Prometheus text format.
+    pub fn encode(&self) -> String {
+        let checkpoint = self.checkpoint.get();
+        if checkpoint > 0 {
+            self.checkpoint_lag
+                .set(Utc::now().timestamp().saturating_sub(checkpoint));
+        }
+
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("the metrics are valid");
+
+        String::from_utf8(buffer).expect("the text format is UTF-8")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_encode() {
+        let metrics = Metrics::new();
+        metrics.domains_fetched.inc_by(3);
+        metrics.error(ErrorKind::Publish);
+        metrics
+            .domains_announced
+            .with_label_values(&["DISCORD"])
+            .inc();
+        metrics.set_checkpoint(Utc::now() - chrono::Duration::seconds(90));
+        metrics.leader.set(1);
+
+        let text = metrics.encode();
+
+        assert!(text.contains("xiler_sync_domains_fetched_total 3"));
+        assert!(text.contains("xiler_sync_errors_total{kind=\"publish\"} 1"));
+        assert!(text.contains("xiler_sync_domains_announced_total{notifier=\"DISCORD\"} 1"));
+        assert!(text.contains("xiler_sync_checkpoint_lag_seconds 9"));
+        assert!(text.contains("xiler_sync_leader 1"));
+        assert!(text.contains("# TYPE xiler_sync_publish_duration_seconds histogram"));
+    }
+}
@@ -0,0 +1,129 @@
+pub mod health;
+pub mod metrics;
+
+use std::{
+    net::{SocketAddr, TcpListener},
+    sync::Arc,
+};
+
+use axum::{http::header, response::IntoResponse, routing::get, Router};
+use color_eyre::eyre::{Result, WrapErr};
+use tracing::{error, info, Instrument};
+
+use health::{get_healthz, get_readyz, Health};
+use metrics::metrics;
+
+/// The state shared by the request handlers.
+#[derive(Clone)]
+pub struct AppState {
+    pub health: Arc<Health>,
+}
+
+/// Starts the HTTP server of the service in the background.
+///
+/// # Errors
+///
+/// Returns an error if the address can not be bound, so a port conflict is reported
+/// on startup.
+pub fn spawn(address: SocketAddr, health: Arc<Health>) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(address)
+        .wrap_err_with(|| format!("Failed to bind the HTTP server to {}", address))?;
+    listener.set_nonblocking(true)?;
+    let address = listener.local_addr()?;
+
+    let server =
+        axum::Server::from_tcp(listener)?.serve(router(AppState { health }).into_make_service());
+
+    tokio::spawn(
+        async move {
+            if let Err(e) = server.await {
+                error!("[SERVER] HTTP server stopped: {:?}", e);
+            }
+        }
+        .in_current_span(),
+    );
+
+    info!("[SERVER] Listening on http://{}", address);
+
+    Ok(address)
+}
+
+fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/metrics", get(get_metrics))
+        .route("/healthz", get(get_healthz))
+        .route("/readyz", get(get_readyz))
+        .with_state(state)
+}
+
+async fn get_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        metrics().encode(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::health::test_health;
+
+    fn spawn_test_server() -> SocketAddr {
+        spawn("127.0.0.1:0".parse().unwrap(), Arc::new(test_health())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn server_serves_metrics() {
+        let address = spawn_test_server();
+
+        let response = reqwest::get(format!("http://{}/metrics", address))
+            .await
+            .unwrap();
+
+        assert!(response.status().is_success());
+        assert_eq!(
+            response.headers()[reqwest::header::CONTENT_TYPE],
+            prometheus::TEXT_FORMAT
+        );
+        assert!(response
+            .text()
+            .await
+            .unwrap()
+            .contains("# TYPE xiler_sync_domains_fetched_total counter"));
+    }
+
+    #[tokio::test]
+    async fn server_serves_healthz() {
+        let address = spawn_test_server();
+
+        let response = reqwest::get(format!("http://{}/healthz", address))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(
+            response.json::<serde_json::Value>().await.unwrap(),
+            serde_json::json!({ "status": "ok" })
+        );
+    }
+
+    #[tokio::test]
+    async fn server_readyz_reports_unreachable_database() {
+        let address = spawn_test_server();
+
+        let response = reqwest::get(format!("http://{}/readyz", address))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = response.json::<serde_json::Value>().await.unwrap();
+        assert_eq!(body["status"], "failing");
+        assert_eq!(body["database"]["status"], "failing");
+        assert!(body["database"]["error"].is_string());
+        assert_eq!(body["publish"]["status"], "ok");
+        assert_eq!(body["publish"]["last_publish"], serde_json::Value::Null);
+        assert_eq!(body["leader"]["status"], "ok");
+        assert_eq!(body["leader"]["current_leader"], serde_json::Value::Null);
+    }
+}
@@ -1,17 +1,72 @@
-use std::env;
+use std::{env, fs};
 
-pub fn ensure_is_not_empty(value: String) -> String {
-    if value.is_empty() {
-        panic!("Value must not be empty");
+use color_eyre::eyre::{Result, WrapErr};
+
+/// Returns the value of an environment variable.
+///
+/// Following the Docker/Kubernetes secrets convention, `KEY_FILE` can point to a
+/// file containing the value. The trimmed file contents take precedence over the
+/// plain `KEY` variable. Unset and empty variables are both treated as missing.
+///
+/// # Errors
+///
+/// Returns an error if `KEY_FILE` is set but the file can not be read.
+pub fn env_var(key: &str) -> Result<Option<String>> {
+    let file_key = format!("{}_FILE", key);
+
+    if let Some(path) = env::var(&file_key).ok().filter(|path| !path.is_empty()) {
+        let contents = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Failed to read {} ({})", file_key, path))?;
+
+        return Ok(Some(contents.trim().to_string()).filter(|value| !value.is_empty()));
     }
 
-    value
+    Ok(env::var(key).ok().filter(|value| !value.is_empty()))
 }
 
-pub fn env_or_default(key: &str, default: &str) -> String {
-    ensure_is_not_empty(env::var(key).unwrap_or(default.to_string()))
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_var_plain() {
+        env::set_var("ENVIRONMENT_TEST_PLAIN", "value");
 
-pub fn env_or_panic(key: &str) -> String {
-    ensure_is_not_empty(env::var(key).unwrap_or_else(|_| panic!("{} must be set", key)))
+        assert_eq!(
+            env_var("ENVIRONMENT_TEST_PLAIN").unwrap(),
+            Some("value".to_string())
+        );
+    }
+
+    #[test]
+    fn env_var_missing_or_empty() {
+        env::set_var("ENVIRONMENT_TEST_EMPTY", "");
+
+        assert_eq!(env_var("ENVIRONMENT_TEST_MISSING").unwrap(), None);
+        assert_eq!(env_var("ENVIRONMENT_TEST_EMPTY").unwrap(), None);
+    }
+
+    #[test]
+    fn env_var_file_takes_precedence() {
+        let path = "./environment.test.secret";
+        fs::write(path, "  secret\n").unwrap();
+
+        env::set_var("ENVIRONMENT_TEST_SECRET", "plain");
+        env::set_var("ENVIRONMENT_TEST_SECRET_FILE", path);
+
+        let value = env_var("ENVIRONMENT_TEST_SECRET");
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(value.unwrap(), Some("secret".to_string()));
+    }
+
+    #[test]
+    fn env_var_file_missing() {
+        env::set_var(
+            "ENVIRONMENT_TEST_UNREADABLE_FILE",
+            "./environment.test.does-not-exist",
+        );
+
+        assert!(env_var("ENVIRONMENT_TEST_UNREADABLE").is_err());
+    }
 }
@@ -0,0 +1,104 @@
+use std::str::FromStr;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+use crate::utils::{environment::env_var, telemetry};
+
+static DEFAULT_LOG_LEVEL: &str = "info";
+
+/// The format of the log lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human readable lines, the default.
+    #[default]
+    Text,
+    /// One JSON object per line, with the fields of the current spans.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = color_eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(eyre!("LOG_FORMAT must be text or json, not {:?}", value)),
+        }
+    }
+}
+
+/// Sets up the logging from the environment.
+///
+/// Logging starts before the configuration is loaded, so it is only configured
+/// through environment variables and not the TOML file.
+///
+/// # Environment variables
+///
+/// * `LOG_FORMAT` - `text` or `json`, defaults to `text`
+/// * `LOG_LEVEL` - A level or filter directives like `info,ordinalswallet=debug`,
+///   falls back to `RUST_LOG` and defaults to `info`
+/// * `OTLP_ENDPOINT` - The OTLP/HTTP collector to export traces to, e.g.
+///   `http://localhost:4318`, traces are not exported without it
+///
+/// # Errors
+///
+/// Returns an error if the format, level or endpoint is invalid.
+pub fn init() -> Result<()> {
+    let format = env_var("LOG_FORMAT")?
+        .map(|format| format.parse())
+        .transpose()?
+        .unwrap_or_default();
+    let level = match env_var("LOG_LEVEL")? {
+        Some(level) => level,
+        None => env_var("RUST_LOG")?.unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string()),
+    };
+
+    let filter = filter(&level)?;
+    let telemetry = env_var("OTLP_ENDPOINT")?
+        .map(|endpoint| telemetry::layer(&endpoint))
+        .transpose()?;
+    let logs = match format {
+        LogFormat::Text => fmt::layer().boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(true)
+            .boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(telemetry)
+        .with(logs)
+        .with(filter)
+        .init();
+
+    Ok(())
+}
+
+fn filter(level: &str) -> Result<EnvFilter> {
+    EnvFilter::builder()
+        .parse(level)
+        .wrap_err_with(|| format!("LOG_LEVEL is not a valid level or filter: {:?}", level))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logging_format() {
+        assert_eq!("text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!("yaml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn logging_filter() {
+        assert!(filter("debug").is_ok());
+        assert!(filter("info,ordinalswallet=debug,sqlx=warn").is_ok());
+        assert!(filter("ordinalswallet=loud").is_err());
+    }
+}
@@ -1,4 +1,10 @@
-pub mod discord_webhook;
 pub mod environment;
 pub mod last_update;
+pub mod logging;
+pub mod preview;
 pub mod request;
+pub mod schedule;
+pub mod secret;
+pub mod shutdown;
+pub mod signature;
+pub mod telemetry;
@@ -0,0 +1,184 @@
+use std::{path::Path, sync::Arc};
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{self, fontdb},
+};
+
+use crate::models::domain_length::DomainLength;
+
+/// The size of the domain cards attached to announcements.
+pub const CARD_SIZE: (u32, u32) = (1200, 630);
+/// The size of the collection thumbnails.
+pub const THUMBNAIL_SIZE: (u32, u32) = (512, 512);
+
+static FONT_FAMILY: &str = "Inter, 'DejaVu Sans', Arial, sans-serif";
+
+/// Renders domain images in the Xiler style: the domain name on a dark gradient with
+/// its length tier.
+#[derive(Clone)]
+pub struct PreviewRenderer {
+    fontdb: Arc<fontdb::Database>,
+}
+
+impl std::fmt::Debug for PreviewRenderer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreviewRenderer")
+            .field("fonts", &self.fontdb.len())
+            .finish()
+    }
+}
+
+impl PreviewRenderer {
+    /// Creates a renderer with the system fonts and the fonts in `fonts_dir`.
+    pub fn new(fonts_dir: Option<&Path>) -> Self {
+        let mut fontdb = fontdb::Database::new();
+        fontdb.load_system_fonts();
+
+        if let Some(dir) = fonts_dir {
+            fontdb.load_fonts_dir(dir);
+        }
+
+        Self {
+            fontdb: Arc::new(fontdb),
+        }
+    }
+
+    /// Renders the card of a domain, as attached to announcements.
+    pub fn card(&self, domain: &str) -> Result<Vec<u8>> {
+        self.render_domain(domain, CARD_SIZE)
+    }
+
+    /// Renders the square thumbnail of a domain, e.g. for the collection.
+    pub fn thumbnail(&self, domain: &str) -> Result<Vec<u8>> {
+        self.render_domain(domain, THUMBNAIL_SIZE)
+    }
+
+    fn render_domain(&self, domain: &str, size: (u32, u32)) -> Result<Vec<u8>> {
+        if domain.len() < 3 || !domain.ends_with(".o") {
+            bail!("Invalid domain {:?}", domain);
+        }
+
+        self.render_png(&svg(domain, size), size)
+    }
+
+    /// Rasterizes a SVG to a PNG of the given size.
+    pub fn render_png(&self, svg: &str, (width, height): (u32, u32)) -> Result<Vec<u8>> {
+        let options = usvg::Options {
+            fontdb: self.fontdb.clone(),
+            ..Default::default()
+        };
+
+        let tree = usvg::Tree::from_str(svg, &options).wrap_err("Invalid preview SVG")?;
+        let mut pixmap = Pixmap::new(width, height).ok_or_else(|| eyre!("Invalid preview size"))?;
+
+        let size = tree.size();
+        let transform =
+            Transform::from_scale(width as f32 / size.width(), height as f32 / size.height());
+        resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+        pixmap.encode_png().wrap_err("Failed to encode the preview")
+    }
+}
+
+/// Returns the SVG of a domain image of the given size.
+///
+/// # Panics
+///
+/// Panics if the domain does not have the .o suffix, see [`DomainLength::from`].
+pub fn svg(domain: &str, (width, height): (u32, u32)) -> String {
+    let length = DomainLength::from(domain);
+    let name = domain.trim_end_matches(".o");
+
+    // Shrink the name to fit the width, assuming glyphs of about 0.6em wide.
+    let characters = domain.chars().count().max(1) as f32;
+    let font_size = (width as f32 * 0.8 / (characters * 0.6)).min(height as f32 * 0.35);
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">
+  <defs>
+    <linearGradient id="background" x1="0" y1="0" x2="1" y2="1">
+      <stop offset="0" stop-color="#0b1320"/>
+      <stop offset="1" stop-color="#16263d"/>
+    </linearGradient>
+  </defs>
+  <rect width="{width}" height="{height}" fill="url(#background)"/>
+  <rect x="0" y="{bar_y}" width="{width}" height="{bar_height}" fill="#3598db"/>
+  <text x="50%" y="50%" text-anchor="middle" dominant-baseline="central" font-family="{FONT_FAMILY}" font-weight="bold" font-size="{font_size:.0}" fill="#ffffff">{name}<tspan fill="#3598db">.o</tspan></text>
+  <text x="50%" y="{tier_y}" text-anchor="middle" font-family="{FONT_FAMILY}" font-size="{tier_size:.0}" fill="#8fa3bf" letter-spacing="4">{tier}</text>
+  <text x="{margin}" y="{margin}" dominant-baseline="hanging" font-family="{FONT_FAMILY}" font-weight="bold" font-size="{tier_size:.0}" fill="#ffffff">XILER</text>
+</svg>"##,
+        bar_y = height - height / 40,
+        bar_height = height / 40,
+        name = escape_xml(name),
+        tier_y = height as f32 * 0.8,
+        tier_size = height as f32 * 0.05,
+        tier = tier_label(length),
+        margin = height / 15,
+    )
+}
+
+fn tier_label(length: DomainLength) -> &'static str {
+    match length {
+        DomainLength::SingleCharacter => "SINGLE CHARACTER",
+        DomainLength::VeryShort => "VERY SHORT",
+        DomainLength::Short => "SHORT",
+        DomainLength::Normal => "NORMAL",
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&apos;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    fn png_size(png: &[u8]) -> (u32, u32) {
+        let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+        let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+        (width, height)
+    }
+
+    #[test]
+    fn preview_svg_is_escaped() {
+        let svg = svg("<&>.o", CARD_SIZE);
+
+        assert!(svg.contains(">&lt;&amp;&gt;<tspan"));
+        assert!(svg.contains(">SHORT<"));
+    }
+
+    #[test]
+    fn preview_card() {
+        let png = PreviewRenderer::new(None).card("xiler.o").unwrap();
+
+        assert!(png.starts_with(PNG_SIGNATURE));
+        assert_eq!(png_size(&png), CARD_SIZE);
+    }
+
+    #[test]
+    fn preview_invalid_domain() {
+        assert!(PreviewRenderer::new(None).card("xiler").is_err());
+    }
+
+    #[test]
+    fn preview_thumbnail() {
+        let png = PreviewRenderer::new(None).thumbnail("x.o").unwrap();
+
+        assert!(png.starts_with(PNG_SIGNATURE));
+        assert_eq!(png_size(&png), THUMBNAIL_SIZE);
+    }
+}
@@ -1,90 +1,99 @@
 use crate::{
-    models::inscription::{Inscription, NewInscriptions},
-    utils::environment::{env_or_default, env_or_panic},
+    config::Config,
+    models::{
+        audit_entry::Receipt,
+        inscription::{Inscription, NewInscriptions},
+    },
+    server::metrics::metrics,
 };
-use lazy_static::lazy_static;
-use tracing::error;
+use reqwest::RequestBuilder;
+use tracing::{field, instrument};
 
-use super::last_update::set_last_update_to_now;
+use super::telemetry::{self, TraceContext};
 
-lazy_static! {
-    static ref API_BASE_URL: String =
-        env_or_default("API_BASE_URL", "https://turbo.ordinalswallet.com/");
-    static ref CREATOR_ADDRESS: String = env_or_panic("CREATOR_ADDRESS");
-    static ref CREATOR_SIGNATURE: String = env_or_panic("CREATOR_SIGNATURE");
-    static ref SLUG: String = env_or_default("SLUG", "xiler-dns");
+/// Returns the URL the inscriptions are published to.
+pub fn publish_url(config: &Config) -> String {
+    format!("{}/collection/update", config.api_base_url)
 }
 
 /// Publishes new inscriptions to the API
 ///
-/// # Environment variables
-///
-/// * `API_BASE_URL` - The base URL of the API
-/// * `CREATOR_ADDRESS` - The address of the creator
-/// * `CREATOR_SIGNATURE` - The signature of the creator
-/// * `SLUG` - The slug of the collection
-///
 /// # Arguments
 ///
+/// * `config` - The configuration holding the API base URL, creator and slug
 /// * `inscriptions` - A vector of new inscriptions to publish
 ///
+/// # Returns
+///
+/// The receipt of the response, which is also returned for an error status, see
+/// [`Receipt::is_success`]. Logging failures and the checkpoint are left to the
+/// caller, which advances it once the announcements are in the outbox.
+///
 /// # Example
 ///
 /// ```
+/// use xiler::config::Config;
 /// use xiler::utils::request::publish_inscriptions;
 /// use xiler::models::inscription::Inscription;
 ///
+/// let config = Config::load().unwrap();
 /// let inscriptions = vec![
 ///     Inscription::new("test".to_string(), "test".to_string(), vec![]),
 ///     Inscription::new("test2".to_string(), "test2".to_string(), vec![]),
 /// ];
 ///
-/// let result = publish_inscriptions(inscriptions).await;
+/// let receipt = publish_inscriptions(&config, inscriptions).await.unwrap();
 ///
-/// assert!(result.is_ok());
+/// assert!(receipt.is_success());
 /// ```
+#[instrument(
+    skip_all,
+    fields(inscriptions = inscriptions.len(), otel.status_message = field::Empty)
+)]
 pub async fn publish_inscriptions(
+    config: &Config,
     inscriptions: Vec<Inscription>,
-) -> reqwest::Result<Result<(), String>> {
+) -> reqwest::Result<Receipt> {
     let new_inscriptions = NewInscriptions::new(
         inscriptions,
-        CREATOR_ADDRESS.to_string(),
-        CREATOR_SIGNATURE.to_string(),
-        SLUG.to_string(),
+        config.creator_address.clone(),
+        config.creator_signature.clone(),
+        config.slug.clone(),
     );
 
     let client = reqwest::Client::new();
-    let url = format!("{}/collection/update", *API_BASE_URL);
 
-    let response = client.post(&url).json(&new_inscriptions).send().await?;
+    let timer = metrics().publish_duration.start_timer();
+    let receipt = execute(client.post(publish_url(config)).json(&new_inscriptions)).await;
+    timer.observe_duration();
 
-    if !response.status().is_success() {
-        error!("Error publishing inscriptions: {:?}", response);
-        let status = response.status();
-        let error = response.text().await?;
-        let error = format!("{}: {}", status, error);
-        return Ok(Err(error));
-    }
+    receipt.inspect_err(telemetry::record_error)
+}
 
-    set_last_update_to_now();
-    Ok(Ok(()))
+/// Sends a request with the trace context of the current span, and reads the
+/// response into a receipt for the audit log.
+///
+/// A response with an error status is not an error, see [`Receipt::is_success`].
+pub async fn execute(request: RequestBuilder) -> reqwest::Result<Receipt> {
+    let (client, request) = request.trace_context().build_split();
+    let request = request?;
+    let payload = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(<[u8]>::to_vec);
+
+    let response = client.execute(request).await?;
+    let status = response.status();
+    let body = response.text().await?;
+
+    Ok(Receipt::new(payload.as_deref(), status.as_u16(), &body))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::test_config;
     use mockito;
-    use std::env;
-
-    static LAST_UPDATE_FILE: &str = "./last_update.timestamp.test";
-
-    #[ctor::ctor]
-    fn setup_env() {
-        env::set_var("CREATOR_ADDRESS", "test");
-        env::set_var("CREATOR_SIGNATURE", "test");
-        env::set_var("SLUG", "test");
-        env::set_var("LAST_UPDATE_FILE", LAST_UPDATE_FILE);
-    }
 
     #[tokio::test]
     async fn test_publish_inscriptions_success() {
@@ -95,11 +104,17 @@ mod tests {
             Inscription::new("test2".to_string(), "test2".to_string(), vec![]),
         ];
 
+        let config = Config {
+            api_base_url: server.url(),
+            last_update_file: std::env::temp_dir().join("last_update.publish.test"),
+            ..test_config()
+        };
+
         let new_inscriptions = NewInscriptions::new(
             inscriptions.clone(),
-            "test".to_string(),
-            "test".to_string(),
-            "test".to_string(),
+            config.creator_address.clone(),
+            config.creator_signature.clone(),
+            config.slug.clone(),
         );
 
         let json = serde_json::to_string(&new_inscriptions).unwrap();
@@ -111,15 +126,47 @@ mod tests {
             .with_status(200)
             .create();
 
-        env::set_var("API_BASE_URL", server.url());
+        let receipt = publish_inscriptions(&config, inscriptions).await.unwrap();
 
-        let result = publish_inscriptions(inscriptions).await;
-
-        assert!(result.is_ok());
-        assert!(result.unwrap().is_ok());
+        assert!(receipt.is_success());
+        assert_eq!(receipt.status, Some(200));
+        assert_eq!(
+            receipt.payload_hash,
+            Receipt::new(Some(json.as_bytes()), 200, "").payload_hash
+        );
 
         mock.assert();
 
-        std::fs::remove_file(LAST_UPDATE_FILE).unwrap();
+        // The checkpoint is only advanced once the announcements are in the outbox.
+        assert!(!config.last_update_file.exists());
+    }
+
+    #[tokio::test]
+    async fn test_publish_inscriptions_error_status() {
+        let mut server = mockito::Server::new_async().await;
+        let config = Config {
+            api_base_url: server.url(),
+            ..test_config()
+        };
+
+        let mock = server
+            .mock("POST", "/collection/update")
+            .with_status(400)
+            .with_body("invalid signature")
+            .create_async()
+            .await;
+
+        let inscriptions = vec![Inscription::new(
+            "test".to_string(),
+            "test".to_string(),
+            vec![],
+        )];
+        let receipt = publish_inscriptions(&config, inscriptions).await.unwrap();
+
+        assert!(!receipt.is_success());
+        assert_eq!(receipt.status, Some(400));
+        assert_eq!(receipt.response.as_deref(), Some("invalid signature"));
+
+        mock.assert_async().await;
     }
 }
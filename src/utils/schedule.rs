@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+
+use crate::{config::Config, server::metrics::metrics};
+
+/// How many seconds polling stays at the minimum interval after a publish.
+const BURST_WINDOW: i64 = 10 * 60;
+/// The poll interval doubles for every this many seconds without a publish.
+const BACKOFF_STEP: i64 = 60 * 60;
+
+/// The bounds of the adaptive poll interval, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: u64,
+    pub max: u64,
+}
+
+/// Decides how long the sync loop sleeps between cycles.
+///
+/// With fixed intervals it always polls every `poll_interval` seconds and retries
+/// after `retry_interval` seconds. With adaptive bounds it polls at the minimum for
+/// a while after a publish to catch the rest of a burst, polls every `poll_interval`
+/// seconds after that, and backs off towards the maximum when idle for hours.
+/// Consecutive failures are then retried with an exponential backoff.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    poll_interval: u64,
+    retry_interval: u64,
+    adaptive: Option<Bounds>,
+    started_at: DateTime<Utc>,
+    last_publish: Option<DateTime<Utc>>,
+    failures: u32,
+}
+
+impl Schedule {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            poll_interval: config.poll_interval,
+            retry_interval: config.retry_interval,
+            adaptive: config.adaptive_polling.then_some(Bounds {
+                min: config.poll_interval_min,
+                max: config.poll_interval_max,
+            }),
+            started_at: Utc::now(),
+            last_publish: None,
+            failures: 0,
+        }
+    }
+
+    /// Records that domains were published.
+    pub fn published(&mut self, now: DateTime<Utc>) {
+        self.last_publish = Some(now);
+        self.failures = 0;
+    }
+
+    /// Records a cycle that finished without errors.
+    pub fn succeeded(&mut self) {
+        self.failures = 0;
+    }
+
+    /// Records a failed step and returns the amount of seconds before retrying.
+    pub fn failed(&mut self) -> u64 {
+        self.failures = self.failures.saturating_add(1);
+        self.retry_interval()
+    }
+
+    /// Returns the amount of seconds before the next poll, and reports it in the
+    /// metrics.
+    pub fn next_poll(&self) -> u64 {
+        let interval = self.poll_interval_at(Utc::now());
+        metrics().poll_interval.set(interval as i64);
+        interval
+    }
+
+    fn poll_interval_at(&self, now: DateTime<Utc>) -> u64 {
+        let Some(bounds) = self.adaptive else {
+            return self.poll_interval;
+        };
+
+        let idle = match self.last_publish {
+            Some(last_publish) => {
+                let idle = (now - last_publish).num_seconds();
+                if idle < BURST_WINDOW {
+                    return bounds.min;
+                }
+                idle
+            }
+            None => (now - self.started_at).num_seconds(),
+        };
+
+        let doublings = (idle.max(0) / BACKOFF_STEP).min(32) as u32;
+        self.poll_interval
+            .saturating_mul(1 << doublings)
+            .clamp(bounds.min, bounds.max)
+    }
+
+    fn retry_interval(&self) -> u64 {
+        let Some(bounds) = self.adaptive else {
+            return self.retry_interval;
+        };
+
+        let doublings = self.failures.saturating_sub(1).min(32);
+        self.retry_interval
+            .saturating_mul(1 << doublings)
+            .min(bounds.max.max(self.retry_interval))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use chrono::Duration;
+
+    fn adaptive() -> Schedule {
+        Schedule::new(&Config {
+            poll_interval: 60,
+            retry_interval: 5,
+            adaptive_polling: true,
+            poll_interval_min: 5,
+            poll_interval_max: 600,
+            ..test_config()
+        })
+    }
+
+    #[test]
+    fn schedule_fixed_intervals() {
+        let mut schedule = Schedule::new(&test_config());
+        let now = schedule.started_at;
+
+        assert_eq!(schedule.poll_interval_at(now), 60);
+        assert_eq!(schedule.poll_interval_at(now + Duration::hours(5)), 60);
+
+        schedule.published(now);
+        assert_eq!(schedule.poll_interval_at(now), 60);
+        assert_eq!(schedule.failed(), 5);
+        assert_eq!(schedule.failed(), 5);
+    }
+
+    #[test]
+    fn schedule_adaptive_poll_interval() {
+        let mut schedule = adaptive();
+        let now = schedule.started_at;
+
+        assert_eq!(schedule.poll_interval_at(now), 60);
+
+        schedule.published(now);
+        assert_eq!(schedule.poll_interval_at(now + Duration::minutes(9)), 5);
+        assert_eq!(schedule.poll_interval_at(now + Duration::minutes(10)), 60);
+        assert_eq!(schedule.poll_interval_at(now + Duration::hours(1)), 120);
+        assert_eq!(schedule.poll_interval_at(now + Duration::hours(3)), 480);
+        assert_eq!(schedule.poll_interval_at(now + Duration::hours(4)), 600);
+        assert_eq!(schedule.poll_interval_at(now + Duration::days(365)), 600);
+    }
+
+    #[test]
+    fn schedule_adaptive_retry_interval() {
+        let mut schedule = adaptive();
+
+        assert_eq!(schedule.failed(), 5);
+        assert_eq!(schedule.failed(), 10);
+        assert_eq!(schedule.failed(), 20);
+        for _ in 0..40 {
+            schedule.failed();
+        }
+        assert_eq!(schedule.failed(), 600);
+
+        schedule.succeeded();
+        assert_eq!(schedule.failed(), 5);
+    }
+}
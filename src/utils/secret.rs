@@ -0,0 +1,110 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A value that must not end up in logs.
+///
+/// Serializes and deserializes as the inner value, but formats as `***` in both
+/// `Debug` and `Display`. Use [`Secret::expose`] to access the inner value.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Returns the inner value.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: FromStr> FromStr for Secret<T> {
+    type Err = T::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "***")
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "***")
+    }
+}
+
+impl<T: Serialize> Serialize for Secret<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "hunter2";
+
+    #[test]
+    fn secret_expose() {
+        let secret = Secret::new(SECRET.to_string());
+
+        assert_eq!(secret.expose(), SECRET);
+    }
+
+    #[test]
+    fn secret_debug_is_redacted() {
+        let secret = Secret::new(SECRET.to_string());
+
+        assert_eq!(format!("{:?}", secret), "***");
+        assert_eq!(format!("{:#?}", Some(&secret)), "Some(\n    ***,\n)");
+    }
+
+    #[test]
+    fn secret_display_is_redacted() {
+        let secret = Secret::new(SECRET.to_string());
+
+        assert_eq!(format!("{}", secret), "***");
+        assert_eq!(secret.to_string(), "***");
+    }
+
+    #[test]
+    fn secret_serialize() {
+        let secret = Secret::new(SECRET.to_string());
+
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"hunter2\"");
+    }
+
+    #[test]
+    fn secret_deserialize() {
+        let secret: Secret<String> = serde_json::from_str("\"hunter2\"").unwrap();
+
+        assert_eq!(secret.expose(), SECRET);
+    }
+
+    #[test]
+    fn secret_from_str() {
+        let secret: Secret<String> = SECRET.parse().unwrap();
+
+        assert_eq!(secret.expose(), SECRET);
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tracing::{error, info};
+
+/// Tells the tasks of the service to stop picking up new work.
+///
+/// Clones share the same state, so one shutdown request reaches every task.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    receiver: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    /// Creates a shutdown that is requested through the returned sender.
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (sender, receiver) = watch::channel(false);
+        (sender, Self { receiver })
+    }
+
+    /// Creates a shutdown that is requested by SIGTERM or SIGINT (Ctrl+C).
+    pub fn listen() -> Self {
+        let (sender, shutdown) = Self::channel();
+
+        tokio::spawn(async move {
+            match wait_for_signal().await {
+                Ok(signal) => {
+                    info!(
+                        "[SHUTDOWN] Received {}, finishing the current cycle",
+                        signal
+                    );
+                    sender.send_replace(true);
+                }
+                Err(e) => error!("[SHUTDOWN] Failed to listen for signals: {:?}", e),
+            }
+        });
+
+        shutdown
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Waits until the shutdown is requested.
+    pub async fn requested(&mut self) {
+        // Without a sender the shutdown can never be requested.
+        if self
+            .receiver
+            .wait_for(|requested| *requested)
+            .await
+            .is_err()
+        {
+            std::future::pending::<()>().await;
+        }
+    }
+
+    /// Sleeps for the given amount of seconds, or until the shutdown is requested.
+    ///
+    /// Returns whether the full amount of seconds was slept.
+    pub async fn sleep(&mut self, seconds: u64) -> bool {
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(seconds)) => true,
+            _ = self.requested() => false,
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> std::io::Result<&'static str> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+
+    tokio::select! {
+        _ = terminate.recv() => Ok("SIGTERM"),
+        _ = interrupt.recv() => Ok("SIGINT"),
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> std::io::Result<&'static str> {
+    tokio::signal::ctrl_c().await?;
+    Ok("Ctrl+C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn shutdown_interrupts_sleep() {
+        let (sender, mut shutdown) = Shutdown::channel();
+
+        assert!(!shutdown.is_requested());
+        assert!(shutdown.sleep(0).await);
+
+        let mut clone = shutdown.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            sender.send_replace(true);
+        });
+
+        assert!(!clone.sleep(60).await);
+        assert!(shutdown.is_requested());
+        assert!(!shutdown.sleep(60).await);
+    }
+
+    #[tokio::test]
+    async fn shutdown_without_sender_is_never_requested() {
+        let (sender, mut shutdown) = Shutdown::channel();
+        drop(sender);
+
+        assert!(shutdown.sleep(1).await);
+        assert!(!shutdown.is_requested());
+    }
+}
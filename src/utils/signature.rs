@@ -0,0 +1,555 @@
+use std::str::FromStr;
+
+use bitcoin::{
+    absolute::LockTime,
+    base64::{prelude::BASE64_STANDARD, Engine},
+    consensus,
+    ecdsa::Signature as EcdsaSignature,
+    hashes::{sha256, Hash, HashEngine},
+    key::{Keypair, TapTweak, XOnlyPublicKey},
+    opcodes::{all::OP_RETURN, OP_0},
+    script::{Builder, PushBytesBuf},
+    secp256k1::{ecdsa::RecoverableSignature, ecdsa::RecoveryId, Message, Secp256k1},
+    sighash::{Prevouts, SighashCache},
+    sign_message::{signed_msg_hash, MessageSignature},
+    taproot::Signature as TaprootSignature,
+    transaction::Version,
+    Address, AddressType, Amount, EcdsaSighashType, Network, OutPoint, PrivateKey, PublicKey,
+    ScriptBuf, Sequence, TapSighashType, Transaction, TxIn, TxOut, Witness,
+};
+use color_eyre::eyre::{bail, eyre, Result};
+use tracing::info;
+
+/// Tag used for the BIP-322 message hash.
+static BIP322_TAG: &[u8] = b"BIP0322-signed-message";
+
+/// BIP-137 header offsets for the different address types.
+const BIP137_P2PKH_UNCOMPRESSED: u8 = 27;
+const BIP137_P2PKH_COMPRESSED: u8 = 31;
+const BIP137_P2SH_P2WPKH: u8 = 35;
+
+/// Verifies that the creator signature was made by the creator address.
+///
+/// Used as a preflight check on startup, so a misconfigured address or signature
+/// is reported before any inscriptions are published.
+///
+/// # Arguments
+///
+/// * `network` - The network of the creator address
+/// * `address` - The creator address
+/// * `message` - The message that should have been signed
+/// * `signature` - The base64 encoded creator signature
+///
+/// # Errors
+///
+/// Returns an error describing which part of the configuration is invalid.
+pub fn verify_creator_signature(
+    network: Network,
+    address: &str,
+    message: &str,
+    signature: &str,
+) -> Result<()> {
+    let parsed = parse_address(address, network).map_err(|e| {
+        eyre!(
+            "CREATOR_ADDRESS `{}` is not a valid {} address: {}",
+            address,
+            network,
+            e
+        )
+    })?;
+
+    match verify_message(&parsed, message, signature) {
+        Ok(true) => {
+            info!("[SIGNATURE] Creator signature verified for {}", address);
+            Ok(())
+        }
+        Ok(false) => bail!(
+            "CREATOR_SIGNATURE was not made by CREATOR_ADDRESS `{}` for the message `{}`",
+            address,
+            message
+        ),
+        Err(e) => bail!("CREATOR_SIGNATURE could not be verified: {}", e),
+    }
+}
+
+/// Loads a WIF encoded private key.
+pub fn load_private_key(wif: &str) -> Result<PrivateKey> {
+    PrivateKey::from_wif(wif.trim()).map_err(|e| eyre!("Invalid creator private key: {}", e))
+}
+
+/// Parses an address of the given network.
+pub fn parse_address(address: &str, network: Network) -> Result<Address> {
+    Ok(Address::from_str(address)?.require_network(network)?)
+}
+
+/// Signs a message for the given address.
+///
+/// Legacy (P2PKH) and nested segwit (P2SH-P2WPKH) addresses are signed using BIP-137,
+/// native segwit (P2WPKH) and taproot (P2TR) addresses using the BIP-322 simple format.
+///
+/// # Arguments
+///
+/// * `private_key` - The private key belonging to the address
+/// * `address` - The address to sign the message for
+/// * `message` - The message to sign
+///
+/// # Returns
+///
+/// The base64 encoded signature.
+pub fn sign_message(private_key: &PrivateKey, address: &Address, message: &str) -> Result<String> {
+    let secp = Secp256k1::new();
+    let public_key = private_key.public_key(&secp);
+    let network = *address.network();
+
+    let (expected, header) = match address.address_type() {
+        // Legacy addresses can belong to an uncompressed key, which the header tells
+        // the verifier to recover.
+        Some(AddressType::P2pkh) => (
+            Address::p2pkh(&public_key, network),
+            Some(match private_key.compressed {
+                true => BIP137_P2PKH_COMPRESSED,
+                false => BIP137_P2PKH_UNCOMPRESSED,
+            }),
+        ),
+        Some(AddressType::P2sh) => (
+            Address::p2shwpkh(&public_key, network)?,
+            Some(BIP137_P2SH_P2WPKH),
+        ),
+        Some(AddressType::P2wpkh) => (Address::p2wpkh(&public_key, network)?, None),
+        Some(AddressType::P2tr) => {
+            let keypair = Keypair::from_secret_key(&secp, &private_key.inner);
+            let (internal_key, _) = keypair.x_only_public_key();
+            (Address::p2tr(&secp, internal_key, None, network), None)
+        }
+        address_type => bail!("Unsupported address type: {:?}", address_type),
+    };
+
+    if expected != *address {
+        bail!("The private key does not belong to {}", address);
+    }
+
+    let Some(header) = header else {
+        return sign_bip322_simple(private_key, address, message);
+    };
+
+    let msg = Message::from_digest(signed_msg_hash(message).to_byte_array());
+    let signature = secp.sign_ecdsa_recoverable(&msg, &private_key.inner);
+    let (recovery_id, compact) = signature.serialize_compact();
+
+    let mut serialized = [0u8; 65];
+    serialized[0] = header + recovery_id.to_i32() as u8;
+    serialized[1..].copy_from_slice(&compact);
+
+    Ok(BASE64_STANDARD.encode(serialized))
+}
+
+/// Verifies that a base64 encoded signature signs the message for the given address.
+///
+/// Both BIP-137 and BIP-322 simple signatures are supported.
+///
+/// # Arguments
+///
+/// * `address` - The address that should have signed the message
+/// * `message` - The signed message
+/// * `signature` - The base64 encoded signature
+pub fn verify_message(address: &Address, message: &str, signature: &str) -> Result<bool> {
+    let bytes = BASE64_STANDARD
+        .decode(signature.trim())
+        .map_err(|e| eyre!("Signature is not valid base64: {}", e))?;
+
+    if bytes.len() == 65 && (27..=42).contains(&bytes[0]) {
+        verify_bip137(address, message, &bytes)
+    } else {
+        verify_bip322_simple(address, message, &bytes)
+    }
+}
+
+fn verify_bip137(address: &Address, message: &str, bytes: &[u8]) -> Result<bool> {
+    let secp = Secp256k1::verification_only();
+    let header = bytes[0] - 27;
+
+    let recovery_id = RecoveryId::from_i32((header & 0x03) as i32)?;
+    let signature = MessageSignature::new(
+        RecoverableSignature::from_compact(&bytes[1..], recovery_id)?,
+        header >= 4,
+    );
+    let public_key = signature.recover_pubkey(&secp, signed_msg_hash(message))?;
+
+    let recovered = match address.address_type() {
+        Some(AddressType::P2pkh) => Address::p2pkh(&public_key, *address.network()),
+        Some(AddressType::P2sh) => Address::p2shwpkh(&public_key, *address.network())?,
+        Some(AddressType::P2wpkh) => Address::p2wpkh(&public_key, *address.network())?,
+        Some(AddressType::P2tr) => {
+            Address::p2tr(&secp, public_key.inner.into(), None, *address.network())
+        }
+        address_type => bail!("Unsupported address type: {:?}", address_type),
+    };
+
+    Ok(recovered == *address)
+}
+
+/// Computes the BIP-322 tagged message hash.
+fn bip322_message_hash(message: &str) -> sha256::Hash {
+    let tag = sha256::Hash::hash(BIP322_TAG);
+
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_byte_array());
+    engine.input(tag.as_byte_array());
+    engine.input(message.as_bytes());
+
+    sha256::Hash::from_engine(engine)
+}
+
+/// Builds the virtual `to_spend` and `to_sign` transactions of BIP-322.
+fn bip322_transactions(script_pubkey: &ScriptBuf, message: &str) -> (TxOut, Transaction) {
+    let message_hash = PushBytesBuf::from(bip322_message_hash(message).to_byte_array());
+
+    let to_spend = Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: Builder::new()
+                .push_opcode(OP_0)
+                .push_slice(message_hash)
+                .into_script(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: script_pubkey.clone(),
+        }],
+    };
+
+    let to_sign = Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(to_spend.txid(), 0),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script(),
+        }],
+    };
+
+    (to_spend.output[0].clone(), to_sign)
+}
+
+fn sign_bip322_simple(
+    private_key: &PrivateKey,
+    address: &Address,
+    message: &str,
+) -> Result<String> {
+    let secp = Secp256k1::new();
+    let (prevout, to_sign) = bip322_transactions(&address.script_pubkey(), message);
+    let mut cache = SighashCache::new(&to_sign);
+
+    let witness = match address.address_type() {
+        Some(AddressType::P2wpkh) => {
+            let sighash = cache.p2wpkh_signature_hash(
+                0,
+                &prevout.script_pubkey,
+                prevout.value,
+                EcdsaSighashType::All,
+            )?;
+            let msg = Message::from_digest(sighash.to_byte_array());
+            let signature = EcdsaSignature::sighash_all(secp.sign_ecdsa(&msg, &private_key.inner));
+
+            Witness::p2wpkh(&signature, &private_key.public_key(&secp).inner)
+        }
+        Some(AddressType::P2tr) => {
+            let sighash = cache.taproot_key_spend_signature_hash(
+                0,
+                &Prevouts::All(&[prevout]),
+                TapSighashType::Default,
+            )?;
+            let msg = Message::from_digest(sighash.to_byte_array());
+            let keypair = Keypair::from_secret_key(&secp, &private_key.inner)
+                .tap_tweak(&secp, None)
+                .to_inner();
+            let signature = TaprootSignature {
+                sig: secp.sign_schnorr(&msg, &keypair),
+                hash_ty: TapSighashType::Default,
+            };
+
+            Witness::from_slice(&[signature.to_vec()])
+        }
+        address_type => bail!("BIP-322 is not supported for {:?}", address_type),
+    };
+
+    Ok(BASE64_STANDARD.encode(consensus::serialize(&witness)))
+}
+
+fn verify_bip322_simple(address: &Address, message: &str, bytes: &[u8]) -> Result<bool> {
+    let secp = Secp256k1::verification_only();
+    let witness: Witness = consensus::deserialize(bytes)
+        .map_err(|e| eyre!("Signature is neither BIP-137 nor BIP-322: {}", e))?;
+
+    let (prevout, to_sign) = bip322_transactions(&address.script_pubkey(), message);
+    let mut cache = SighashCache::new(&to_sign);
+
+    match address.address_type() {
+        Some(AddressType::P2wpkh) => {
+            let (Some(signature), Some(public_key), 2) =
+                (witness.nth(0), witness.nth(1), witness.len())
+            else {
+                return Ok(false);
+            };
+
+            let signature = EcdsaSignature::from_slice(signature)?;
+            let public_key = PublicKey::from_slice(public_key)?;
+
+            if Address::p2wpkh(&public_key, *address.network())? != *address {
+                return Ok(false);
+            }
+
+            let sighash = cache.p2wpkh_signature_hash(
+                0,
+                &prevout.script_pubkey,
+                prevout.value,
+                signature.hash_ty,
+            )?;
+            let msg = Message::from_digest(sighash.to_byte_array());
+
+            Ok(secp
+                .verify_ecdsa(&msg, &signature.sig, &public_key.inner)
+                .is_ok())
+        }
+        Some(AddressType::P2tr) => {
+            let (Some(signature), 1) = (witness.nth(0), witness.len()) else {
+                return Ok(false);
+            };
+
+            let signature = TaprootSignature::from_slice(signature)?;
+            let output_key = XOnlyPublicKey::from_slice(&prevout.script_pubkey.as_bytes()[2..])?;
+
+            let sighash = cache.taproot_key_spend_signature_hash(
+                0,
+                &Prevouts::All(&[&prevout]),
+                signature.hash_ty,
+            )?;
+            let msg = Message::from_digest(sighash.to_byte_array());
+
+            Ok(secp
+                .verify_schnorr(&signature.sig, &msg, &output_key)
+                .is_ok())
+        }
+        address_type => bail!("BIP-322 is not supported for {:?}", address_type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors from BIP-322.
+    const PRIVATE_KEY: &str = "L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k";
+    const P2WPKH_ADDRESS: &str = "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l";
+    const P2TR_ADDRESS: &str = "bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3";
+    const P2WPKH_SIGNATURE: &str = "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+    const P2TR_SIGNATURE: &str = "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==";
+
+    fn private_key() -> PrivateKey {
+        PrivateKey::from_wif(PRIVATE_KEY).unwrap()
+    }
+
+    fn address_of_type(address_type: AddressType) -> Address {
+        let secp = Secp256k1::new();
+        let public_key = private_key().public_key(&secp);
+
+        match address_type {
+            AddressType::P2pkh => Address::p2pkh(&public_key, Network::Bitcoin),
+            AddressType::P2sh => Address::p2shwpkh(&public_key, Network::Bitcoin).unwrap(),
+            AddressType::P2wpkh => parse_address(P2WPKH_ADDRESS, Network::Bitcoin).unwrap(),
+            AddressType::P2tr => parse_address(P2TR_ADDRESS, Network::Bitcoin).unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn bip322_message_hash_vectors() {
+        assert_eq!(
+            bip322_message_hash("").to_string(),
+            "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+        );
+        assert_eq!(
+            bip322_message_hash("Hello World").to_string(),
+            "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a"
+        );
+    }
+
+    #[test]
+    fn verify_bip322_p2wpkh_vector() {
+        let address = parse_address(P2WPKH_ADDRESS, Network::Bitcoin).unwrap();
+
+        assert!(verify_message(&address, "Hello World", P2WPKH_SIGNATURE).unwrap());
+        assert!(!verify_message(&address, "", P2WPKH_SIGNATURE).unwrap());
+    }
+
+    #[test]
+    fn verify_bip322_p2tr_vector() {
+        let address = parse_address(P2TR_ADDRESS, Network::Bitcoin).unwrap();
+
+        assert!(verify_message(&address, "Hello World", P2TR_SIGNATURE).unwrap());
+        assert!(!verify_message(&address, "", P2TR_SIGNATURE).unwrap());
+    }
+
+    #[test]
+    fn sign_and_verify_all_address_types() {
+        for address_type in [
+            AddressType::P2pkh,
+            AddressType::P2sh,
+            AddressType::P2wpkh,
+            AddressType::P2tr,
+        ] {
+            let address = address_of_type(address_type);
+            let signature = sign_message(&private_key(), &address, "xiler-dns").unwrap();
+
+            assert!(
+                verify_message(&address, "xiler-dns", &signature).unwrap(),
+                "{:?} signature should verify",
+                address_type
+            );
+            assert!(
+                !verify_message(&address, "other", &signature).unwrap(),
+                "{:?} signature should not verify another message",
+                address_type
+            );
+        }
+    }
+
+    #[test]
+    fn sign_and_verify_uncompressed_p2pkh() {
+        let private_key = PrivateKey {
+            compressed: false,
+            ..private_key()
+        };
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2pkh(&public_key, Network::Bitcoin);
+        let signature = sign_message(&private_key, &address, "xiler-dns").unwrap();
+
+        let header = BASE64_STANDARD.decode(&signature).unwrap()[0];
+        assert!((BIP137_P2PKH_UNCOMPRESSED..BIP137_P2PKH_COMPRESSED).contains(&header));
+        assert!(verify_message(&address, "xiler-dns", &signature).unwrap());
+
+        // The address of the compressed key is another address.
+        assert!(sign_message(
+            &private_key,
+            &address_of_type(AddressType::P2pkh),
+            "xiler-dns"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn sign_with_foreign_private_key() {
+        let address = parse_address(
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            Network::Bitcoin,
+        )
+        .unwrap();
+
+        assert!(sign_message(&private_key(), &address, "xiler-dns").is_err());
+    }
+
+    #[test]
+    fn verify_signature_of_other_address() {
+        let address = address_of_type(AddressType::P2pkh);
+        let signature = sign_message(&private_key(), &address, "xiler-dns").unwrap();
+        let other = parse_address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2", Network::Bitcoin).unwrap();
+
+        assert!(!verify_message(&other, "xiler-dns", &signature).unwrap());
+    }
+
+    #[test]
+    fn verify_creator_signature_valid() {
+        assert!(verify_creator_signature(
+            Network::Bitcoin,
+            P2TR_ADDRESS,
+            "Hello World",
+            P2TR_SIGNATURE
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn verify_creator_signature_wrong_message() {
+        let error =
+            verify_creator_signature(Network::Bitcoin, P2TR_ADDRESS, "xiler-dns", P2TR_SIGNATURE)
+                .unwrap_err()
+                .to_string();
+
+        assert!(error.contains("was not made by CREATOR_ADDRESS"));
+    }
+
+    #[test]
+    fn verify_creator_signature_wrong_address() {
+        let error = verify_creator_signature(
+            Network::Bitcoin,
+            P2WPKH_ADDRESS,
+            "Hello World",
+            P2TR_SIGNATURE,
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(error.contains("was not made by CREATOR_ADDRESS"));
+    }
+
+    #[test]
+    fn verify_creator_signature_invalid_address() {
+        let error =
+            verify_creator_signature(Network::Bitcoin, "test", "Hello World", P2TR_SIGNATURE)
+                .unwrap_err()
+                .to_string();
+
+        assert!(error.contains("CREATOR_ADDRESS `test` is not a valid bitcoin address"));
+    }
+
+    #[test]
+    fn verify_creator_signature_other_network() {
+        let error =
+            verify_creator_signature(Network::Signet, P2TR_ADDRESS, "Hello World", P2TR_SIGNATURE)
+                .unwrap_err()
+                .to_string();
+
+        assert!(error.contains("is not a valid signet address"));
+    }
+
+    #[test]
+    fn sign_and_verify_signet() {
+        let secp = Secp256k1::new();
+        let public_key = private_key().public_key(&secp);
+        let address = Address::p2wpkh(&public_key, Network::Signet).unwrap();
+        let signature = sign_message(&private_key(), &address, "xiler-dns").unwrap();
+
+        assert!(verify_creator_signature(
+            Network::Signet,
+            &address.to_string(),
+            "xiler-dns",
+            &signature
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn verify_creator_signature_invalid_signature() {
+        let error = verify_creator_signature(Network::Bitcoin, P2TR_ADDRESS, "Hello World", "test")
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("CREATOR_SIGNATURE could not be verified"));
+    }
+
+    #[test]
+    fn verify_invalid_base64() {
+        let address = address_of_type(AddressType::P2pkh);
+
+        assert!(verify_message(&address, "xiler-dns", "not base64!").is_err());
+    }
+}
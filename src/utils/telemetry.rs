@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{Result, WrapErr};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{self, Tracer, TracerProvider},
+    Resource,
+};
+use tracing::Span;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::{
+    filter::{filter_fn, FilterFn, Filtered},
+    registry::LookupSpan,
+    Layer,
+};
+
+/// The name of the service in the exported traces.
+const SERVICE_NAME: &str = "ordinalswallet-sync";
+
+/// Creates a tracer provider that exports spans over OTLP/HTTP in batches.
+///
+/// # Arguments
+///
+/// * `endpoint` - The base URL of the collector, e.g. `http://localhost:4318`,
+///   the spans are sent to its `/v1/traces` path
+pub fn provider(endpoint: &str) -> Result<TracerProvider> {
+    let exporter = opentelemetry_otlp::new_exporter()
+        .http()
+        .with_endpoint(endpoint)
+        .build_span_exporter()
+        .wrap_err_with(|| format!("Failed to create the OTLP exporter for {}", endpoint))?;
+
+    Ok(TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_config(
+            trace::config()
+                .with_resource(Resource::new([KeyValue::new("service.name", SERVICE_NAME)])),
+        )
+        .build())
+}
+
+/// Creates the layer that exports the spans of the service to the given collector.
+///
+/// It also makes outgoing requests carry the W3C trace context, see
+/// [`TraceContext`].
+pub fn layer<S>(endpoint: &str) -> Result<impl Layer<S>>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    let provider = provider(endpoint)?;
+    let tracer = opentelemetry::trace::TracerProvider::tracer(&provider, SERVICE_NAME);
+
+    global::set_tracer_provider(provider);
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    Ok(traces(tracer))
+}
+
+/// The layer turning spans into traces.
+///
+/// The sync span lasts as long as the process, so it is left out and every cycle
+/// and delivery is a trace of its own. Logs still carry its fields.
+fn traces<S>(tracer: Tracer) -> Filtered<OpenTelemetryLayer<S, Tracer>, FilterFn, S>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer()
+        .with_tracer(tracer)
+        .with_filter(filter_fn(|metadata| metadata.name() != "sync"))
+}
+
+/// Exports the remaining spans, blocking until they are sent.
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
+
+/// Marks the current span as failed in the exported traces.
+///
+/// The span must declare an empty `otel.status_message` field.
+pub fn record_error(error: &impl std::fmt::Display) {
+    Span::current().record("otel.status_message", error.to_string());
+}
+
+/// Propagates the trace context of the current span to an outgoing request.
+pub trait TraceContext {
+    fn trace_context(self) -> Self;
+}
+
+impl TraceContext for reqwest::RequestBuilder {
+    /// Adds the `traceparent` header of the current span. Without an OTLP endpoint
+    /// no propagator is installed and the request is left as is.
+    fn trace_context(self) -> Self {
+        let mut headers = HashMap::new();
+        global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&Span::current().context(), &mut headers)
+        });
+
+        headers
+            .into_iter()
+            .fold(self, |request, (name, value)| request.header(name, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{test_config, Config},
+        models::inscription::Inscription,
+        utils::request::publish_inscriptions,
+    };
+    use axum::{body::Bytes, extract::State, routing::post, Router};
+    use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+    use opentelemetry_proto::tonic::{
+        collector::trace::v1::ExportTraceServiceRequest, trace::v1::Span as ExportedSpan,
+    };
+    use prost::Message;
+    use std::{
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{info_span, Instrument};
+    use tracing_subscriber::prelude::*;
+
+    type Collected = Arc<Mutex<Vec<ExportedSpan>>>;
+
+    /// Stands in for an OTLP collector, keeping the spans it receives.
+    fn spawn_collector() -> (SocketAddr, Collected) {
+        let collected = Collected::default();
+
+        let app = Router::new()
+            .route(
+                "/v1/traces",
+                post(
+                    |State(collected): State<Collected>, body: Bytes| async move {
+                        let request = ExportTraceServiceRequest::decode(body).unwrap();
+                        collected.lock().unwrap().extend(
+                            request
+                                .resource_spans
+                                .into_iter()
+                                .flat_map(|resource| resource.scope_spans)
+                                .flat_map(|scope| scope.spans),
+                        );
+                    },
+                ),
+            )
+            .with_state(collected.clone());
+
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let address = server.local_addr();
+        tokio::spawn(server);
+
+        (address, collected)
+    }
+
+    // The batch exporter runs on the runtime while the flush blocks, so it needs a
+    // second thread.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn telemetry_exports_spans_and_propagates_trace_context() {
+        let (collector, collected) = spawn_collector();
+        let provider = provider(&format!("http://{}", collector)).unwrap();
+        let subscriber = tracing_subscriber::registry().with(traces(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let mut api = mockito::Server::new_async().await;
+        let config = Config {
+            api_base_url: api.url(),
+            ..test_config()
+        };
+
+        let sync = info_span!("sync");
+        let cycle = sync.in_scope(|| info_span!("cycle"));
+        let trace_id = cycle.context().span().span_context().trace_id();
+
+        let mock = api
+            .mock("POST", "/collection/update")
+            .match_header(
+                "traceparent",
+                mockito::Matcher::Regex(format!("^00-{}-[0-9a-f]{{16}}-01$", trace_id)),
+            )
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let inscriptions = vec![Inscription::new(
+            "test".to_string(),
+            "test".to_string(),
+            vec![],
+        )];
+        publish_inscriptions(&config, inscriptions)
+            .instrument(cycle)
+            .await
+            .unwrap();
+        drop(sync);
+
+        mock.assert_async().await;
+        tokio::task::spawn_blocking(move || provider.force_flush())
+            .await
+            .unwrap();
+
+        let spans = collected.lock().unwrap();
+        let span = |name: &str| {
+            spans
+                .iter()
+                .find(|span| span.name == name)
+                .unwrap_or_else(|| panic!("the {} span is exported", name))
+        };
+
+        // The sync span is left out, so the cycle starts the trace.
+        assert!(spans.iter().all(|span| span.name != "sync"));
+        assert!(span("cycle").parent_span_id.is_empty());
+        assert_eq!(span("cycle").trace_id, trace_id.to_bytes());
+        assert_eq!(
+            span("publish_inscriptions").parent_span_id,
+            span("cycle").span_id
+        );
+    }
+}